@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use aoc2017::helpers::parse::{self, Span};
+use aoc2017::helpers::trace::Filter;
+use aoc2017::helpers::Solution;
+
+pub const CONFIG_FILE: &str = "aoc.toml";
+
+pub const USAGE: &str = "\
+Usage: aoc2017 [DAYS] [OPTIONS]
+
+DAYS is `all`, a day (`7`), a range (`3-9`) or a comma separated mix.
+
+Options:
+  --help               show this message
+  --config FILE        read defaults from FILE instead of ./aoc.toml
+  --inputs DIR         directory with the puzzle inputs (dayNN.in)
+  --examples-dir DIR   directory with the examples (exNN.in)
+  --examples           solve the examples instead of the inputs
+  --format FORMAT      `text` or `json`
+  --timeout SECS       give up on a day after SECS seconds
+  --threads N          solve up to N days in parallel
+  --answers FILE       check results against FILE (`day part1 part2` per line)
+  --determinism N      solve every day N times and report differing answers
+  --load N             keep N busy threads running during --determinism
+  --no-summary         skip the summary table at the end
+  --report             show the details some days give beyond their answers
+  --export DIR         write the pictures some days draw to DIR (dayNN.dot, ...)
+  --trace FILTER       trace days while solving, e.g. `day18` or `day06=info,day14`
+  --trace-file FILE    write the trace to FILE instead of stderr";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub input_dir: PathBuf,
+    pub example_dir: PathBuf,
+    pub use_examples: bool,
+    pub days: Vec<u8>,
+    pub format: Format,
+    pub timeout: Option<Duration>,
+    pub threads: usize,
+    pub answers: Option<PathBuf>,
+    pub determinism: Option<usize>,
+    pub load: usize,
+    pub summary: bool,
+    pub report: bool,
+    pub export_dir: Option<PathBuf>,
+    pub trace: Option<Filter>,
+    pub trace_file: Option<PathBuf>,
+}
+
+pub type Answers = HashMap<u8, Solution>;
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            input_dir: PathBuf::from("inputs"),
+            example_dir: PathBuf::from("examples"),
+            use_examples: false,
+            days: (1..26).collect(),
+            format: Format::Text,
+            timeout: None,
+            threads: 1,
+            answers: None,
+            determinism: None,
+            load: 0,
+            summary: true,
+            report: false,
+            export_dir: None,
+            trace: None,
+            trace_file: None,
+        }
+    }
+}
+
+impl Config {
+    /// Defaults, overridden by the config file, overridden by `args`.
+    pub fn load(args: &[String]) -> Result<Config, String> {
+        let mut config = Config::default();
+
+        let explicit = args
+            .iter()
+            .position(|arg| arg == "--config")
+            .map(|i| args.get(i + 1).ok_or("--config needs a value"))
+            .transpose()?;
+
+        match explicit {
+            Some(path) => config.apply_file(Path::new(path))?,
+            None if Path::new(CONFIG_FILE).exists() => config.apply_file(Path::new(CONFIG_FILE))?,
+            None => (),
+        }
+
+        config.apply_args(args)?;
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to access {}: {}", path.display(), e))?;
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+        self.apply_table(table, &path.display().to_string())
+    }
+
+    fn apply_table(&mut self, table: toml::Table, source: &str) -> Result<(), String> {
+        for (key, value) in table {
+            let invalid = || format!("Invalid value for `{}` in {}", key, source);
+            match (key.as_str(), value) {
+                ("input_dir", toml::Value::String(s)) => self.input_dir = s.into(),
+                ("example_dir", toml::Value::String(s)) => self.example_dir = s.into(),
+                ("examples", toml::Value::Boolean(b)) => self.use_examples = b,
+                ("days", toml::Value::String(s)) => self.days = parse_days(&s)?,
+                ("days", toml::Value::Array(v)) => {
+                    self.days = v
+                        .iter()
+                        .map(|x| {
+                            x.as_integer()
+                                .and_then(|x| u8::try_from(x).ok())
+                                .filter(|d| (1..=25).contains(d))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                ("format", toml::Value::String(s)) => self.format = parse_format(&s)?,
+                ("timeout", toml::Value::Integer(n)) if n > 0 => {
+                    self.timeout = Some(Duration::from_secs(n as u64))
+                }
+                ("timeout", toml::Value::Float(n)) => {
+                    self.timeout = Some(parse_timeout(n).ok_or_else(invalid)?)
+                }
+                ("threads", toml::Value::Integer(n)) if n > 0 => self.threads = n as usize,
+                ("answers", toml::Value::String(s)) => self.answers = Some(s.into()),
+                ("determinism", toml::Value::Integer(n)) if n > 0 => {
+                    self.determinism = Some(n as usize)
+                }
+                ("load", toml::Value::Integer(n)) if n >= 0 => self.load = n as usize,
+                ("summary", toml::Value::Boolean(b)) => self.summary = b,
+                ("report", toml::Value::Boolean(b)) => self.report = b,
+                ("export_dir", toml::Value::String(s)) => self.export_dir = Some(s.into()),
+                ("trace", toml::Value::String(s)) => self.trace = Some(s.parse()?),
+                ("trace_file", toml::Value::String(s)) => self.trace_file = Some(s.into()),
+                (
+                    "input_dir" | "example_dir" | "examples" | "days" | "format" | "timeout"
+                    | "threads" | "answers" | "determinism" | "load" | "summary" | "report"
+                    | "export_dir" | "trace" | "trace_file",
+                    _,
+                ) => return Err(invalid()),
+                (key, _) => return Err(format!("Unknown key `{}` in {}", key, source)),
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--config" => {
+                    value()?;
+                }
+                "--inputs" => self.input_dir = value()?.into(),
+                "--examples-dir" => self.example_dir = value()?.into(),
+                "--examples" => self.use_examples = true,
+                "--no-summary" => self.summary = false,
+                "--report" => self.report = true,
+                "--format" => self.format = parse_format(value()?)?,
+                "--timeout" => {
+                    self.timeout = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .and_then(parse_timeout)
+                            .ok_or("--timeout needs a positive number of seconds")?,
+                    )
+                }
+                "--threads" => {
+                    self.threads = value()?
+                        .parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or("--threads needs a positive number")?
+                }
+                "--answers" => self.answers = Some(value()?.into()),
+                "--determinism" => {
+                    self.determinism = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or("--determinism needs a positive number")?,
+                    )
+                }
+                "--load" => self.load = value()?.parse().map_err(|_| "--load needs a number")?,
+                "--export" => self.export_dir = Some(value()?.into()),
+                "--trace" => self.trace = Some(value()?.parse()?),
+                "--trace-file" => self.trace_file = Some(value()?.into()),
+                flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+                days => self.days = parse_days(days)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    match s {
+        "text" => Ok(Format::Text),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("Unknown format `{}`", s)),
+    }
+}
+
+/// A positive number of seconds that fits in a `Duration`.
+fn parse_timeout(secs: f64) -> Option<Duration> {
+    (secs > 0.0).then(|| Duration::try_from_secs_f64(secs).ok())?
+}
+
+fn parse_days(s: &str) -> Result<Vec<u8>, String> {
+    if s == "all" {
+        return Ok((1..26).collect());
+    }
+    let mut days = Vec::new();
+    for part in s.split(',') {
+        let parse_day = |d: &str| {
+            d.trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|d| (1..=25).contains(d))
+                .ok_or_else(|| format!("Invalid day `{}`, expected 1 to 25", d.trim()))
+        };
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (parse_day(from)?, parse_day(to)?);
+                if from > to {
+                    return Err(format!("Invalid range `{}`, {} comes after {}", part, from, to));
+                }
+                days.extend(from..=to)
+            }
+            None => days.push(parse_day(part)?),
+        }
+    }
+    Ok(days)
+}
+
+pub fn load_answers(path: &Path) -> Result<Answers, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to access {}: {}", path.display(), e))?;
+    let lines = parse::parse_lines(&text, |line| {
+        let [day, part1, part2] = line.split_whitespace().collect::<Vec<Span>>()[..] else {
+            return Err(line.error("expected `day part1 part2`"));
+        };
+        Ok((
+            day.parse()?,
+            [part1.as_str().to_string(), part2.as_str().to_string()],
+        ))
+    });
+    lines
+        .map(|lines| lines.into_iter().collect())
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_days() {
+        assert_eq!(parse_days("7"), Ok(vec![7]));
+        assert_eq!(parse_days("1-3,9"), Ok(vec![1, 2, 3, 9]));
+        assert!(parse_days("1-x").is_err());
+        assert!(parse_days("5-3").is_err());
+        assert!(parse_days("0").is_err());
+        assert!(parse_days("20-26").is_err());
+        assert_eq!(parse_days("25"), Ok(vec![25]));
+    }
+
+    #[test]
+    fn test_args() {
+        let mut config = Config::default();
+        config
+            .apply_args(&args("3-4 --format json --threads 2 --timeout 1.5"))
+            .unwrap();
+        assert_eq!(config.days, vec![3, 4]);
+        assert_eq!(config.format, Format::Json);
+        assert_eq!(config.threads, 2);
+        assert_eq!(config.timeout, Some(Duration::from_millis(1500)));
+        assert!(config.apply_args(&args("--colour")).is_err());
+        assert!(config.apply_args(&args("--timeout 0")).is_err());
+        assert!(config.apply_args(&args("--timeout inf")).is_err());
+        assert!(config.apply_args(&args("--timeout NaN")).is_err());
+
+        config
+            .apply_args(&args("--determinism 5 --load 3"))
+            .unwrap();
+        assert_eq!((config.determinism, config.load), (Some(5), 3));
+        assert!(config.apply_args(&args("--determinism 0")).is_err());
+
+        config.apply_args(&args("--trace day18=debug")).unwrap();
+        assert!(config.trace.is_some());
+        assert!(config.apply_args(&args("--trace day18=loud")).is_err());
+
+        config.apply_args(&args("--report --export out")).unwrap();
+        assert_eq!(config.export_dir, Some(PathBuf::from("out")));
+    }
+
+    #[test]
+    fn test_table() {
+        let mut config = Config::default();
+        let table = "input_dir = \"data\"\ndays = [1, 5]\ntimeout = 2"
+            .parse()
+            .unwrap();
+        config.apply_table(table, "test").unwrap();
+        assert_eq!(config.input_dir, PathBuf::from("data"));
+        assert_eq!(config.days, vec![1, 5]);
+        assert_eq!(config.timeout, Some(Duration::from_secs(2)));
+
+        let table = "days = [1, 26]".parse().unwrap();
+        assert!(config.apply_table(table, "test").is_err());
+        let table = "timeout = 1e30".parse().unwrap();
+        assert!(config.apply_table(table, "test").is_err());
+        let table = "timeout = 0".parse().unwrap();
+        assert!(config.apply_table(table, "test").is_err());
+        let table = "threads = \"many\"".parse().unwrap();
+        assert!(config.apply_table(table, "test").is_err());
+        let table = "colour = true".parse().unwrap();
+        assert!(config.apply_table(table, "test").is_err());
+    }
+}
@@ -1,43 +1,140 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
+
+type Captcha = Vec<u8>;
 
-type Captcha = Vec<u32>;
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    NotADigit { position: u64, byte: u8 },
+}
 
-fn parse_input(s: DayString) -> Captcha {
-    s.chars().map(|x| x.to_digit(10).unwrap()).collect()
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "{}", e),
+            StreamError::NotADigit { position, byte } => write!(
+                f,
+                "byte {}: expected a digit, found `{}`",
+                position,
+                byte.escape_ascii()
+            ),
+        }
+    }
 }
 
-fn solve_part1(captcha: &Captcha) -> u32 {
-    let mut sum: u32 = 0;
-    let n = captcha.len();
-    for i in 0..n {
-        let this = captcha[i];
-        let next = captcha[(i + 1) % n];
-        sum += ((this == next) as u32) * this
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
     }
-    sum
 }
 
-fn solve_part2(captcha: &Captcha) -> u32 {
-    let n = captcha.len();
-    (0..n)
-        .map(|i| {
-            let this = captcha[i];
-            let next = captcha[(i + n / 2) % n];
-            ((this == next) as u32) * this
-        })
-        .sum::<u32>()
+fn parse_input(s: &str) -> ParseResult<Captcha> {
+    let digits = parse::digits(Span::new(s))?;
+    Ok(digits.into_iter().map(|d| d as u8).collect())
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+/// Sum of the digits that match the digit `offset` places further on,
+/// wrapping around at the end.
+pub fn captcha_sum(digits: &[u8], offset: usize) -> u64 {
+    if digits.is_empty() {
+        return 0;
+    }
+    let offset = offset % digits.len();
+    digits
+        .iter()
+        .zip(digits.iter().cycle().skip(offset))
+        .filter(|(a, b)| a == b)
+        .map(|(&d, _)| d as u64)
+        .sum()
+}
+
+/// `captcha_sum` over a stream of ASCII digits, keeping no more than
+/// `2 * offset` of them in memory, and only as many as have been read for
+/// short streams. A trailing line break is allowed.
+pub fn captcha_sum_stream<R: BufRead>(mut reader: R, offset: usize) -> Result<u64, StreamError> {
+    // The first `offset` digits, needed again to wrap around at the end,
+    // and the last `offset` digits, indexed by position modulo `offset`.
+    // Both grow as digits arrive, the ring starts once the head is full.
+    let mut head = Vec::new();
+    let mut ring = Vec::new();
+    let mut sum = 0;
+    let mut n = 0;
+    let mut position = 0;
+    let mut ended = false;
+
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for &byte in buf {
+            match byte {
+                b'0'..=b'9' if !ended => {
+                    let d = byte - b'0';
+                    if offset == 0 {
+                        sum += d as u64;
+                    } else if n < offset {
+                        head.push(d);
+                        if head.len() == offset {
+                            ring.clone_from(&head);
+                        }
+                    } else {
+                        let slot = n % offset;
+                        if ring[slot] == d {
+                            sum += d as u64;
+                        }
+                        ring[slot] = d;
+                    }
+                    n += 1;
+                }
+                b'\n' | b'\r' => ended = true,
+                _ => return Err(StreamError::NotADigit { position, byte }),
+            }
+            position += 1;
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+
+    if n < offset {
+        return Ok(captcha_sum(&head, offset));
+    }
+    for (j, &first) in head.iter().enumerate() {
+        let last = ring[(n - offset + j) % offset];
+        if last == first {
+            sum += last as u64;
+        }
+    }
+    Ok(sum)
+}
+
+fn solve_part1(captcha: &Captcha) -> u64 {
+    captcha_sum(captcha, 1)
+}
+
+fn solve_part2(captcha: &Captcha) -> u64 {
+    captcha_sum(captcha, captcha.len() / 2)
+}
+
+pub fn solve_str(s: &str) -> ParseResult<(u64, u64)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(1))
+    let (sol1, sol2) = solve_str(helpers::read_day(1)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -46,15 +143,15 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        assert_eq!(parse_input("1122"), [1, 1, 2, 2]);
+        assert_eq!(parse_input("1122").unwrap(), [1, 1, 2, 2]);
     }
 
     #[test]
     fn test_part1() {
-        let ex1 = parse_input("1122");
-        let ex2 = parse_input("1111");
-        let ex3 = parse_input("1234");
-        let ex4 = parse_input("91212129");
+        let ex1 = parse_input("1122").unwrap();
+        let ex2 = parse_input("1111").unwrap();
+        let ex3 = parse_input("1234").unwrap();
+        let ex4 = parse_input("91212129").unwrap();
 
         assert_eq!(solve_part1(&ex1), 3);
         assert_eq!(solve_part1(&ex2), 4);
@@ -64,11 +161,11 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        let ex1 = parse_input("1212");
-        let ex2 = parse_input("1221");
-        let ex3 = parse_input("123425");
-        let ex4 = parse_input("123123");
-        let ex5 = parse_input("12131415");
+        let ex1 = parse_input("1212").unwrap();
+        let ex2 = parse_input("1221").unwrap();
+        let ex3 = parse_input("123425").unwrap();
+        let ex4 = parse_input("123123").unwrap();
+        let ex5 = parse_input("12131415").unwrap();
 
         assert_eq!(solve_part2(&ex1), 6);
         assert_eq!(solve_part2(&ex2), 0);
@@ -76,4 +173,23 @@ mod tests {
         assert_eq!(solve_part2(&ex4), 12);
         assert_eq!(solve_part2(&ex5), 4);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = "91212129123425";
+        let digits = parse_input(input).unwrap();
+        for offset in [0, 1, 3, 7, 13, 14, 20] {
+            let reader = io::BufReader::with_capacity(3, input.as_bytes());
+            let sum = captcha_sum_stream(reader, offset).unwrap();
+            assert_eq!(sum, captcha_sum(&digits, offset), "offset {}", offset);
+        }
+        assert_eq!(captcha_sum_stream(&b"1122\n"[..], 1).unwrap(), 3);
+        let sum = captcha_sum_stream(&b"1212"[..], usize::MAX).unwrap();
+        assert_eq!(sum, captcha_sum(&[1, 2, 1, 2], usize::MAX));
+
+        let err = captcha_sum_stream(&b"12a3"[..], 1).unwrap_err();
+        assert!(matches!(err, StreamError::NotADigit { position: 2, byte: b'a' }));
+        let err = captcha_sum_stream(&b"12\n3"[..], 1).unwrap_err();
+        assert!(matches!(err, StreamError::NotADigit { position: 3, .. }));
+    }
 }
@@ -1,58 +1,215 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
-
-type Spreadsheet = Vec<Vec<u32>>;
-fn parse_input(s: DayString) -> Spreadsheet {
-    fn parse_line(line: &str) -> Vec<u32> {
-        line.split(char::is_whitespace)
-            .map(|x| x.parse().unwrap())
-            .collect()
-    }
+use crate::helpers::parse::{self, ParseError, ParseResult, Span};
+use crate::helpers::Solution;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Row {
+    /// Line of the input the row was read from.
+    pub line: usize,
+    pub cells: Vec<u32>,
+}
 
-    s.lines().map(parse_line).collect()
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Spreadsheet {
+    pub header: Option<Vec<String>>,
+    pub rows: Vec<Row>,
 }
 
-fn solve_part1(sheet: &Spreadsheet) -> u32 {
-    fn row_diff(row: &[u32]) -> Option<u32> {
-        let max = row.iter().max()?;
-        let min = row.iter().min()?;
-        Some(max - min)
+/// Rule for the value each row adds to the checksum. Closures over a row
+/// work as rules too.
+pub trait RowChecksum {
+    fn row_value(&self, row: &[u32]) -> Result<u32, String>;
+
+    /// Fails with the line of the first row without a value. Sums in `u64`,
+    /// which no sheet that fits in memory can overflow.
+    fn checksum(&self, sheet: &Spreadsheet) -> ParseResult<u64> {
+        sheet
+            .rows
+            .iter()
+            .map(|row| {
+                self.row_value(&row.cells)
+                    .map(u64::from)
+                    .map_err(|msg| ParseError {
+                        line: row.line,
+                        column: 1,
+                        msg,
+                    })
+            })
+            .sum()
     }
+}
+
+/// Difference between the largest and the smallest value.
+pub struct MaxMinDiff;
 
-    sheet
-        .iter()
-        .filter_map(|x| row_diff(x.as_slice()))
-        .sum::<u32>()
+/// Quotient of the two values that divide evenly.
+pub struct EvenlyDivisible;
+
+impl RowChecksum for MaxMinDiff {
+    fn row_value(&self, row: &[u32]) -> Result<u32, String> {
+        let max = row.iter().max().ok_or("row is empty")?;
+        let min = row.iter().min().ok_or("row is empty")?;
+        Ok(max - min)
+    }
 }
 
-fn solve_part2(sheet: &Spreadsheet) -> u32 {
-    fn row_diff(row: &Vec<u32>) -> Option<u32> {
-        let n = row.len();
-        for i in 0..n {
-            for j in i + 1..n {
-                let n = row[i];
-                let m = row[j];
-                let (n, m) = (u32::max(n, m), u32::min(n, m));
-                if (n % m) == 0 {
-                    return Some(n / m);
+impl RowChecksum for EvenlyDivisible {
+    /// Looks up the multiples of every distinct value in the sorted row, or
+    /// tests the larger values directly when there are fewer of those.
+    /// A zero divides nothing but is divided evenly by every other value.
+    fn row_value(&self, row: &[u32]) -> Result<u32, String> {
+        let mut values: Vec<u32> = row.iter().copied().filter(|&v| v != 0).collect();
+        values.sort_unstable();
+        let zeros = row.len() - values.len();
+
+        let mut counts: Vec<(u32, usize)> = Vec::new();
+        for v in values {
+            match counts.last_mut() {
+                Some((last, count)) if *last == v => *count += 1,
+                _ => counts.push((v, 1)),
+            }
+        }
+        let lookup: HashMap<u32, usize> = counts.iter().copied().collect();
+        let max = counts.last().map_or(0, |&(v, _)| v as u64);
+
+        let mut n_pairs = zeros * (row.len() - zeros);
+        let mut quotient = 0;
+        for (i, &(v, count)) in counts.iter().enumerate() {
+            if count > 1 {
+                n_pairs += count * (count - 1) / 2;
+                quotient = 1;
+            }
+            let larger = &counts[i + 1..];
+            if max / v as u64 <= larger.len() as u64 {
+                for m in (2 * v as u64..=max).step_by(v as usize) {
+                    if let Some(&c) = lookup.get(&(m as u32)) {
+                        n_pairs += count * c;
+                        quotient = m as u32 / v;
+                    }
                 }
+            } else {
+                for &(w, c) in larger.iter().filter(|&&(w, _)| w % v == 0) {
+                    n_pairs += count * c;
+                    quotient = w / v;
+                }
+            }
+            if n_pairs > 1 {
+                return Err("more than one evenly divisible pair".to_string());
             }
         }
-        None
+
+        match n_pairs {
+            1 => Ok(quotient),
+            _ => Err("no evenly divisible pair".to_string()),
+        }
     }
+}
 
-    sheet.iter().filter_map(row_diff).sum::<u32>()
+impl<F: Fn(&[u32]) -> Result<u32, String>> RowChecksum for F {
+    fn row_value(&self, row: &[u32]) -> Result<u32, String> {
+        self(row)
+    }
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+/// Reads cells separated by commas on lines that have any, by whitespace
+/// on the others. A first line without a single number is taken as the
+/// header. Blank lines are only allowed at the end.
+pub fn parse_sheet(s: &str) -> ParseResult<Spreadsheet> {
+    let mut sheet = Spreadsheet::default();
+    let mut blank = None;
+
+    for (i, line) in parse::lines(s).enumerate() {
+        let line = line.trim();
+        if line.as_str().is_empty() {
+            blank = blank.or(Some(line));
+            continue;
+        }
+        if let Some(blank) = blank {
+            return Err(blank.error("blank line inside the sheet"));
+        }
+        let cells: Vec<Span> = if line.as_str().contains(',') {
+            line.split(",").collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+
+        let is_first = sheet.header.is_none() && sheet.rows.is_empty();
+        if is_first && cells.iter().all(|cell| cell.parse::<u32>().is_err()) {
+            sheet.header = Some(cells.iter().map(|cell| cell.as_str().to_string()).collect());
+            continue;
+        }
+
+        let cells = cells
+            .iter()
+            .enumerate()
+            .map(|(col, cell)| {
+                cell.as_str().parse().map_err(|_| {
+                    cell.error(format!(
+                        "cell {} is not a number: `{}`",
+                        col + 1,
+                        cell.as_str()
+                    ))
+                })
+            })
+            .collect::<ParseResult<_>>()?;
+        sheet.rows.push(Row { line: i + 1, cells });
+    }
+    Ok(sheet)
+}
+
+fn solve_part1(sheet: &Spreadsheet) -> ParseResult<u64> {
+    MaxMinDiff.checksum(sheet)
+}
+
+fn solve_part2(sheet: &Spreadsheet) -> ParseResult<u64> {
+    EvenlyDivisible.checksum(sheet)
+}
+
+pub fn solve_str(s: &str) -> ParseResult<(u64, u64)> {
+    let parsed = parse_sheet(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed))?;
+    let sol2 = helpers::timed(|| solve_part2(&parsed))?;
+    Ok((sol1, sol2))
+}
+
+/// What every row adds to both checksums.
+pub fn report_str(s: &str) -> ParseResult<String> {
+    let sheet = parse_sheet(s)?;
+    let show = |value: Result<u32, String>| value.map_or("-".to_string(), |v| v.to_string());
+    let total = |sum: ParseResult<u64>| sum.map_or("-".to_string(), |v| v.to_string());
+
+    let mut out = format!("{:>6} {:>10} {:>10}\n", "Line", "Max-min", "Divisible");
+    for row in &sheet.rows {
+        let _ = writeln!(
+            out,
+            "{:>6} {:>10} {:>10}",
+            row.line,
+            show(MaxMinDiff.row_value(&row.cells)),
+            show(EvenlyDivisible.row_value(&row.cells))
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{:>6} {:>10} {:>10}",
+        "Total",
+        total(solve_part1(&sheet)),
+        total(solve_part2(&sheet))
+    );
+    for row in &sheet.rows {
+        if let Err(msg) = EvenlyDivisible.row_value(&row.cells) {
+            let _ = writeln!(out, "line {}: {}", row.line, msg);
+        }
+    }
+    out.truncate(out.trim_end().len());
+    Ok(out)
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(2))
+    let (sol1, sol2) = solve_str(helpers::read_day(2)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -61,13 +218,83 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let ex1: Spreadsheet = parse_input(helpers::read_example("02"));
-        assert_eq!(solve_part1(&ex1), 18);
+        let ex1: Spreadsheet = parse_sheet(helpers::read_example("02")).unwrap();
+        assert_eq!(solve_part1(&ex1), Ok(18));
+
+        let sheet = parse_sheet("1 4294967295\n1 4294967295").unwrap();
+        assert_eq!(solve_part1(&sheet), Ok(2 * 4294967294));
     }
 
     #[test]
     fn test_part2() {
-        let ex1: Spreadsheet = parse_input(helpers::read_example("02"));
-        assert_eq!(solve_part2(&ex1), 9);
+        let ex1: Spreadsheet = parse_sheet(helpers::read_example("02")).unwrap();
+        assert_eq!(solve_part2(&ex1), Ok(9));
+
+        assert_eq!(
+            EvenlyDivisible.row_value(&[0, 7, 3, 21]),
+            Err("more than one evenly divisible pair".to_string())
+        );
+        assert_eq!(
+            EvenlyDivisible.row_value(&[5, 7, 11]),
+            Err("no evenly divisible pair".to_string())
+        );
+        assert_eq!(
+            EvenlyDivisible.row_value(&[0, 5, 7]),
+            Err("more than one evenly divisible pair".to_string())
+        );
+        assert_eq!(EvenlyDivisible.row_value(&[7, 0]), Ok(0));
+        assert_eq!(
+            EvenlyDivisible.row_value(&[0, 0, 3, 9, 0]),
+            Err("more than one evenly divisible pair".to_string())
+        );
+        assert_eq!(
+            EvenlyDivisible.row_value(&[0, 0]),
+            Err("no evenly divisible pair".to_string())
+        );
+
+        let sheet = parse_sheet("5 9 2 8\n3 5 7").unwrap();
+        assert_eq!(solve_part2(&sheet).unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_wide_row() {
+        let mut row: Vec<u32> = (50_001..=60_000).rev().collect();
+        row.push(25_003);
+        assert_eq!(EvenlyDivisible.row_value(&row), Ok(2));
+        row.push(1);
+        assert!(EvenlyDivisible.row_value(&row).is_err());
+    }
+
+    #[test]
+    fn test_parse_sheet() {
+        let tabs = parse_sheet("5\t9 2  8\n9 4 7 3\n\n").unwrap();
+        let csv = parse_sheet("a, b, c, d\n5, 9, 2, 8\n9,4,7,3\n").unwrap();
+        let cells = |sheet: &Spreadsheet| -> Vec<Vec<u32>> {
+            sheet.rows.iter().map(|row| row.cells.clone()).collect()
+        };
+        assert_eq!(cells(&tabs), cells(&csv));
+        assert_eq!(tabs.rows[1].line, 2);
+        assert_eq!(csv.header.as_ref().map(Vec::len), Some(4));
+        assert_eq!(csv.rows[1].line, 3);
+
+        let err = parse_sheet("1,2,3\n4,,6").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(err.msg.contains("cell 2"));
+
+        // The delimiter is chosen per line.
+        let mixed = parse_sheet("1 2 3\n4,5,6").unwrap();
+        assert_eq!(cells(&mixed), [[1, 2, 3], [4, 5, 6]]);
+        let err = parse_sheet("1 2\n\n3 4").unwrap_err();
+        assert_eq!(
+            (err.line, err.msg.as_str()),
+            (2, "blank line inside the sheet")
+        );
+    }
+
+    #[test]
+    fn test_custom_checksum() {
+        let sheet = parse_sheet(helpers::read_example("02")).unwrap();
+        let sum = |row: &[u32]| Ok(row.iter().sum());
+        assert_eq!(sum.checksum(&sheet), Ok(24 + 23 + 22));
     }
 }
@@ -1,51 +1,195 @@
+use std::collections::HashMap;
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::geom::{Dir, Point2};
+use crate::helpers::parse::{ParseResult, Span};
+use crate::helpers::Solution;
+use crate::SolveError;
+
+type Square = u64;
+type Pos = Point2<i64>;
 
-type Square = i32;
+/// The spiral memory: square 1 at the origin, 2 to its right, then
+/// counter-clockwise outwards. `y` grows downwards, so 3 is at `(1, -1)`.
+pub struct Spiral;
+
+/// Squares with their positions in spiral order, found by walking.
+pub struct SpiralIter {
+    sq: Square,
+    pos: Pos,
+    dir: Dir,
+    leg: u64,
+    left: u64,
+    turns: u64,
+}
 
-fn parse_input(s: DayString) -> Square {
-    s.parse().unwrap()
+fn parse_input(s: &str) -> ParseResult<Square> {
+    Span::new(s).trim().parse()
 }
 
-fn next_odd_root(sq: Square) -> i32 {
-    let next_root = f64::from(sq).sqrt().ceil() as i32;
+/// Smallest odd number whose square is at least `sq`, the side length of
+/// the ring `sq` lies on.
+fn next_odd_root(sq: Square) -> u64 {
+    let root = sq.isqrt();
+    let next_root = if root * root < sq { root + 1 } else { root };
     next_root + 1 - (next_root % 2)
 }
 
-fn distance(sq: Square) -> i32 {
-    if sq == 1 {
+fn distance(sq: Square) -> u64 {
+    if sq <= 1 {
         return 0;
     }
     let next_root = next_odd_root(sq);
     let ring = next_root / 2;
+    let back = (next_root as u128 * next_root as u128 - sq as u128) as u64;
+    ring + (back % (2 * ring)).abs_diff(ring)
+}
+
+impl Spiral {
+    /// Position of square `sq`, `None` for 0.
+    pub fn coord_of(sq: Square) -> Option<Pos> {
+        match sq {
+            0 => return None,
+            1 => return Some(Point2::default()),
+            _ => (),
+        }
+        let next_root = next_odd_root(sq);
+        let ring = (next_root / 2) as i64;
+        let side = 2 * ring;
+
+        // Walk backwards from the bottom right corner, where the ring ends.
+        let back = (next_root as u128 * next_root as u128 - sq as u128) as i64;
+        let along = back % side;
+        Some(match back / side {
+            0 => Point2::new(ring - along, ring),
+            1 => Point2::new(-ring, ring - along),
+            2 => Point2::new(-ring + along, -ring),
+            _ => Point2::new(ring, -ring + along),
+        })
+    }
+
+    /// Square at `pos`, `None` if its number does not fit into 64 bits.
+    pub fn index_of(pos: Pos) -> Option<Square> {
+        // In 128 bits, so the ring of `i64::MIN` does not overflow.
+        let (x, y) = (pos.x as i128, pos.y as i128);
+        let ring = x.abs().max(y.abs());
+        let side = 2 * ring;
+        let back = if y == ring && x > -ring {
+            ring - x
+        } else if x == -ring && y > -ring {
+            side + ring - y
+        } else if y == -ring && x < ring {
+            2 * side + x + ring
+        } else {
+            3 * side + y + ring
+        };
+        let end = (2 * ring + 1).checked_pow(2)?;
+        u64::try_from(end - back).ok()
+    }
 
-    let ring_end = next_root.pow(2);
-    let circ_order = (ring_end - sq) % (2 * ring);
-    ring + (circ_order - ring).abs()
+    pub fn iter() -> SpiralIter {
+        SpiralIter {
+            sq: 1,
+            pos: Point2::default(),
+            dir: Dir::Right,
+            leg: 1,
+            left: 1,
+            turns: 0,
+        }
+    }
+
+    /// The squares within `radius` of `center`, one row per line.
+    pub fn render(center: Pos, radius: i64) -> String {
+        let rows: Vec<Vec<String>> = (center.y - radius..=center.y + radius)
+            .map(|y| {
+                (center.x - radius..=center.x + radius)
+                    .map(|x| {
+                        Spiral::index_of(Point2::new(x, y))
+                            .map_or("?".to_string(), |sq| sq.to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = rows.iter().flatten().map(String::len).max().unwrap_or(0);
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| format!("{:>width$}", cell))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-fn solve_part1(square: Square) -> i32 {
+impl Iterator for SpiralIter {
+    type Item = (Square, Pos);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sq == 0 {
+            return None;
+        }
+        let item = (self.sq, self.pos);
+        self.sq = self.sq.wrapping_add(1);
+        self.pos += self.dir;
+        self.left -= 1;
+        if self.left == 0 {
+            self.dir = self.dir.turn_left();
+            self.turns += 1;
+            if self.turns.is_multiple_of(2) {
+                self.leg += 1;
+            }
+            self.left = self.leg;
+        }
+        Some(item)
+    }
+}
+
+fn solve_part1(square: Square) -> u64 {
     distance(square)
 }
 
-fn solve_part2(_square: Square) -> i32 {
-    266330
+/// First value larger than `limit` when every square, in spiral order, is
+/// given the sum of the values already written around it. `None` when that
+/// value does not fit in a `u64`.
+fn solve_part2(limit: Square) -> Option<u64> {
+    let mut written: HashMap<Pos, u64> = HashMap::from([(Point2::default(), 1)]);
+    for (_, pos) in Spiral::iter().skip(1) {
+        let value = pos
+            .surrounding()
+            .iter()
+            .filter_map(|p| written.get(p))
+            .try_fold(0u64, |sum, &v| sum.checked_add(v))?;
+        if value > limit {
+            return Some(value);
+        }
+        written.insert(pos, value);
+    }
+    None
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(parsed).to_string();
-    let sol2 = solve_part2(parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> Result<(u64, u64), SolveError> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(parsed));
+    let sol2 = helpers::timed(|| solve_part2(parsed)).ok_or_else(|| {
+        SolveError::Solve(format!(
+            "the first value above {} does not fit in 64 bits",
+            parsed
+        ))
+    })?;
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(3))
+    let (sol1, sol2) = solve_str(helpers::read_day(3)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::geom::Norm;
 
     #[test]
     fn test_part1() {
@@ -56,5 +200,56 @@ mod tests {
     }
 
     #[test]
-    fn test_part2() {}
+    fn test_coord() {
+        let coord = |sq| Spiral::coord_of(sq).unwrap();
+        assert_eq!(coord(2), Point2::new(1, 0));
+        assert_eq!(coord(5), Point2::new(-1, -1));
+        assert_eq!(coord(10), Point2::new(2, 1));
+        assert_eq!(coord(24), Point2::new(1, 2));
+        assert_eq!(coord(49).chebyshev(), 3);
+        assert_eq!(Spiral::coord_of(0), None);
+    }
+
+    #[test]
+    fn test_spiral() {
+        for (sq, pos) in Spiral::iter().take(2_000_000) {
+            assert_eq!(Spiral::coord_of(sq), Some(pos));
+            assert_eq!(Spiral::index_of(pos), Some(sq));
+            assert_eq!(pos.manhattan() as u64, distance(sq));
+        }
+
+        let mut sq: u64 = 1;
+        for _ in 0..1_000_000 {
+            sq = sq
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let pos = Spiral::coord_of(sq.max(1)).unwrap();
+            assert_eq!(Spiral::index_of(pos), Some(sq.max(1)));
+            assert_eq!(pos.manhattan() as u64, distance(sq.max(1)));
+        }
+        let far = Spiral::coord_of(u64::MAX).unwrap();
+        assert_eq!(Spiral::index_of(far), Some(u64::MAX));
+        assert_eq!(Spiral::index_of(Point2::new(1 << 33, 0)), None);
+        for extreme in [i64::MIN, i64::MAX] {
+            assert_eq!(Spiral::index_of(Point2::new(extreme, 0)), None);
+            assert_eq!(Spiral::index_of(Point2::new(3, extreme)), None);
+            assert_eq!(Spiral::index_of(Point2::new(extreme, extreme)), None);
+        }
+
+        assert_eq!(Spiral::render(Point2::default(), 1), "5 4 3\n6 1 2\n7 8 9");
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(solve_part2(1), Some(2));
+        assert_eq!(solve_part2(2), Some(4));
+        assert_eq!(solve_part2(5), Some(10));
+        assert_eq!(solve_part2(26), Some(54));
+        assert_eq!(solve_part2(747), Some(806));
+        assert_eq!(solve_part2(u64::MAX), None);
+        assert!(matches!(
+            solve_str("18446744073709551615"),
+            Err(SolveError::Solve(_))
+        ));
+    }
 }
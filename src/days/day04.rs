@@ -1,48 +1,238 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use unicode_segmentation::UnicodeSegmentation;
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult};
+use crate::helpers::Solution;
 
 type PassPhrase<'a> = Vec<&'a str>;
-type Input = Vec<PassPhrase<'static>>;
+type Input<'a> = Vec<PassPhrase<'a>>;
+
+/// Rule a passphrase broke, with the words that broke it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation<'a> {
+    Duplicate(&'a str, &'a str),
+    Anagram(&'a str, &'a str),
+    TooShort(&'a str),
+    Charset(&'a str, char),
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Duplicate(a, b) => write!(f, "duplicate words `{}` and `{}`", a, b),
+            Violation::Anagram(a, b) => write!(f, "`{}` and `{}` are anagrams", a, b),
+            Violation::TooShort(word) => write!(f, "`{}` is too short", word),
+            Violation::Charset(word, c) => write!(f, "`{}` contains `{}`", word, c),
+        }
+    }
+}
+
+pub trait PassphrasePolicy {
+    /// First rule `phrase` breaks, if any.
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>>;
+
+    /// Policy that is broken when either of the two is.
+    fn and<P: PassphrasePolicy>(self, other: P) -> Both<Self, P>
+    where
+        Self: Sized,
+    {
+        Both(self, other)
+    }
+}
+
+pub struct Both<P, Q>(P, Q);
+
+/// No word may appear twice, optionally ignoring case.
+pub struct NoDuplicates {
+    pub ignore_case: bool,
+}
+
+/// No word may be an anagram of another.
+pub struct NoAnagrams {
+    pub signing: Signing,
+}
+
+/// What a word is made of when looking for anagrams.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Signing {
+    /// Fixed size signatures for ASCII words, graphemes for the rest.
+    #[default]
+    Auto,
+    /// `char`s, so combining marks are letters of their own.
+    Chars,
+    /// Grapheme clusters, so an accented letter stays one letter.
+    Graphemes,
+}
+
+/// Key on which anagrams of a word collide.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Signature<'a> {
+    /// Letter counts of a lowercase ASCII word.
+    Letters([u8; 26]),
+    /// Sorted bytes of a short ASCII word.
+    Ascii { len: u8, bytes: [u8; 32] },
+    /// Sorted chars or graphemes of any other word.
+    Pieces(Vec<&'a str>),
+}
+
+impl<'a> Signature<'a> {
+    pub fn of(word: &'a str, signing: Signing) -> Self {
+        match signing {
+            Signing::Auto => Signature::letters(word)
+                .or_else(|| Signature::ascii(word))
+                .unwrap_or_else(|| Signature::pieces(word.graphemes(true))),
+            Signing::Chars => Signature::pieces(word.split_inclusive(|_| true)),
+            Signing::Graphemes => Signature::pieces(word.graphemes(true)),
+        }
+    }
+
+    fn letters(word: &str) -> Option<Self> {
+        if word.len() > u8::MAX as usize {
+            return None;
+        }
+        let mut counts = [0; 26];
+        for b in word.bytes() {
+            if !b.is_ascii_lowercase() {
+                return None;
+            }
+            counts[(b - b'a') as usize] += 1;
+        }
+        Some(Signature::Letters(counts))
+    }
+
+    fn ascii(word: &str) -> Option<Self> {
+        let mut bytes = [0; 32];
+        if !word.is_ascii() || word.len() > bytes.len() {
+            return None;
+        }
+        bytes[..word.len()].copy_from_slice(word.as_bytes());
+        bytes[..word.len()].sort_unstable();
+        Some(Signature::Ascii {
+            len: word.len() as u8,
+            bytes,
+        })
+    }
+
+    fn pieces(pieces: impl Iterator<Item = &'a str>) -> Self {
+        let mut pieces: Vec<&str> = pieces.collect();
+        pieces.sort_unstable();
+        Signature::Pieces(pieces)
+    }
+}
+
+/// Every word needs at least this many characters.
+pub struct MinLength(pub usize);
+
+/// Every character must pass the test.
+pub struct Charset(pub fn(char) -> bool);
+
+impl<P: PassphrasePolicy, Q: PassphrasePolicy> PassphrasePolicy for Both<P, Q> {
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>> {
+        self.0.check(phrase).or_else(|| self.1.check(phrase))
+    }
+}
 
-fn parse_input(s: DayString) -> Input {
-    s.lines().map(|l| l.trim().split(' ').collect()).collect()
+/// First pair of words with the same key.
+fn collision<'a, K: Hash + Eq>(
+    phrase: &[&'a str],
+    key: impl Fn(&'a str) -> K,
+) -> Option<(&'a str, &'a str)> {
+    let mut seen = HashMap::new();
+    phrase
+        .iter()
+        .find_map(|&word| seen.insert(key(word), word).map(|first| (first, word)))
 }
 
-fn no_duplicates<T: AsRef<str>>(phrase: &Vec<T>) -> bool {
-    let mut new_phrase: Vec<&str> = phrase.iter().map(T::as_ref).collect();
-    new_phrase.sort_unstable();
-    new_phrase.dedup();
-    new_phrase.len() == phrase.len()
+impl PassphrasePolicy for NoDuplicates {
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>> {
+        let pair = if self.ignore_case {
+            collision(phrase, str::to_lowercase)
+        } else {
+            collision(phrase, |word| word)
+        };
+        pair.map(|(a, b)| Violation::Duplicate(a, b))
+    }
 }
 
-fn anagram_free(phrase: &PassPhrase) -> bool {
-    fn word_order(word: &str) -> String {
-        let mut new_word: Vec<char> = word.chars().collect();
-        new_word.sort();
-        new_word.into_iter().collect()
+impl PassphrasePolicy for NoAnagrams {
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>> {
+        collision(phrase, |word| Signature::of(word, self.signing))
+            .map(|(a, b)| Violation::Anagram(a, b))
     }
+}
 
-    let new_words: Vec<String> = phrase.iter().copied().map(word_order).collect();
-    no_duplicates(&new_words)
+impl PassphrasePolicy for MinLength {
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>> {
+        phrase
+            .iter()
+            .find(|word| word.chars().count() < self.0)
+            .map(|word| Violation::TooShort(word))
+    }
+}
+
+impl PassphrasePolicy for Charset {
+    fn check<'a>(&self, phrase: &[&'a str]) -> Option<Violation<'a>> {
+        phrase.iter().find_map(|word| {
+            word.chars()
+                .find(|&c| !(self.0)(c))
+                .map(|c| Violation::Charset(word, c))
+        })
+    }
+}
+
+fn parse_input(s: &str) -> ParseResult<Input<'_>> {
+    parse::parse_lines(s, |line| {
+        let words: PassPhrase = line.split_whitespace().map(|word| word.as_str()).collect();
+        match words.is_empty() {
+            true => Err(line.error("empty passphrase")),
+            false => Ok(words),
+        }
+    })
+}
+
+/// Lines of `s` that break `policy`, numbered from 1.
+pub fn validate<'a>(
+    s: &'a str,
+    policy: &impl PassphrasePolicy,
+) -> ParseResult<Vec<(usize, Violation<'a>)>> {
+    Ok(parse_input(s)?
+        .iter()
+        .enumerate()
+        .filter_map(|(i, phrase)| policy.check(phrase).map(|v| (i + 1, v)))
+        .collect())
+}
+
+fn count_valid(input: &Input, policy: &impl PassphrasePolicy) -> usize {
+    input.iter().filter(|p| policy.check(p).is_none()).count()
 }
 
 fn solve_part1(input: &Input) -> usize {
-    input.iter().filter(|&p| no_duplicates(p)).count()
+    count_valid(input, &NoDuplicates { ignore_case: false })
 }
 
 fn solve_part2(input: &Input) -> usize {
-    input.iter().filter(|&p| anagram_free(p)).count()
+    count_valid(
+        input,
+        &NoAnagrams {
+            signing: Signing::Auto,
+        },
+    )
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(4))
+    let (sol1, sol2) = solve_str(helpers::read_day(4)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -51,13 +241,72 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("04-1"));
+        let input = parse_input(helpers::read_example("04-1")).unwrap();
         assert_eq!(solve_part1(&input), 2);
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("04-2"));
+        let input = parse_input(helpers::read_example("04-2")).unwrap();
         assert_eq!(solve_part2(&input), 3);
     }
+
+    #[test]
+    fn test_policies() {
+        let anagrams = NoAnagrams {
+            signing: Signing::Auto,
+        };
+        let violations = validate(helpers::read_example("04-2"), &anagrams).unwrap();
+        assert_eq!(
+            violations,
+            [
+                (2, Violation::Anagram("abcde", "ecdab")),
+                (5, Violation::Anagram("oiii", "ioii"))
+            ]
+        );
+
+        let policy = NoDuplicates { ignore_case: true }
+            .and(MinLength(3))
+            .and(Charset(|c| c.is_ascii_alphabetic()));
+        let lines = "correct Horse battery horse\nok staple\nthe b4ttery";
+        assert_eq!(
+            validate(lines, &policy).unwrap(),
+            [
+                (1, Violation::Duplicate("Horse", "horse")),
+                (2, Violation::TooShort("ok")),
+                (3, Violation::Charset("b4ttery", '4'))
+            ]
+        );
+        assert_eq!(
+            validate(lines, &NoDuplicates { ignore_case: false }),
+            Ok(vec![])
+        );
+        let err = validate("aa bb\n  \ncc", &policy).unwrap_err();
+        assert_eq!((err.line, err.msg.as_str()), (2, "empty passphrase"));
+    }
+
+    #[test]
+    fn test_signature() {
+        let sig = |word, signing| Signature::of(word, signing);
+        assert!(matches!(
+            sig("listen", Signing::Auto),
+            Signature::Letters(_)
+        ));
+        assert_eq!(sig("Listen", Signing::Auto), sig("enList", Signing::Auto));
+        assert_ne!(sig("Listen", Signing::Auto), sig("enlist", Signing::Auto));
+        assert!(matches!(
+            sig("Listen", Signing::Auto),
+            Signature::Ascii { .. }
+        ));
+
+        // `é` and `á` spelled with combining accents.
+        let (a, b) = ("e\u{301}a", "a\u{301}e");
+        assert_eq!(sig(a, Signing::Chars), sig(b, Signing::Chars));
+        assert_ne!(sig(a, Signing::Graphemes), sig(b, Signing::Graphemes));
+        assert_eq!(sig(a, Signing::Auto), sig(a, Signing::Graphemes));
+        assert_eq!(
+            sig("ñu\u{301}", Signing::Auto),
+            sig("u\u{301}ñ", Signing::Auto)
+        );
+    }
 }
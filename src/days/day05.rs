@@ -1,49 +1,215 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 
 type Program = Vec<i32>;
 
-fn parse_input(s: DayString) -> Program {
-    s.lines().flat_map(str::parse).collect()
+/// How an offset changes after a jump from it.
+pub trait UpdateRule {
+    fn update(&self, offset: i32) -> i32;
 }
 
-fn solve_part1(input: &Program) -> usize {
-    let mut program = input.clone();
-    let mut pointer: i32 = 0;
-    let mut counter = 0;
-    while let Some(offset) = program.get_mut(pointer as usize) {
-        counter += 1;
-        pointer += *offset;
-        *offset += 1;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Every offset grows by one.
+    Increment,
+    /// Offsets of three or more shrink by one, the others grow by one.
+    Strange,
+}
+
+impl UpdateRule for Rule {
+    fn update(&self, offset: i32) -> i32 {
+        match self {
+            Rule::Increment => offset + 1,
+            Rule::Strange if offset >= 3 => offset - 1,
+            Rule::Strange => offset + 1,
+        }
     }
-    counter
 }
 
-fn solve_part2(input: &Program) -> usize {
-    let mut program = input.clone();
-    let mut pointer: i32 = 0;
-    let mut counter = 0;
-    while let Some(offset) = program.get_mut(pointer as usize) {
-        counter += 1;
-        pointer += *offset;
-        if *offset >= 3 {
-            *offset -= 1
-        } else {
-            *offset += 1
+impl<F: Fn(i32) -> i32> UpdateRule for F {
+    fn update(&self, offset: i32) -> i32 {
+        self(offset)
+    }
+}
+
+/// End of the maze the pointer left through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeResult {
+    Escaped {
+        steps: usize,
+        exit: Exit,
+    },
+    /// The pointer came back to `at` without any offset having changed in
+    /// between, so it will keep going round forever.
+    Loops {
+        steps: usize,
+        at: usize,
+    },
+    /// Gave up after the step limit.
+    Unfinished {
+        steps: usize,
+    },
+}
+
+/// Jumps through `maze` until the pointer leaves it, updating every offset
+/// jumped from with `rule`.
+pub fn run_maze(maze: &[i32], rule: impl UpdateRule, limit: Option<usize>) -> MazeResult {
+    let mut maze = maze.to_vec();
+    let mut pointer: i64 = 0;
+    let mut steps = 0;
+    // Positions visited since an offset last changed.
+    let mut unchanged = HashSet::new();
+
+    while let Some(offset) = usize::try_from(pointer).ok().and_then(|i| maze.get_mut(i)) {
+        if limit.is_some_and(|limit| steps >= limit) {
+            return MazeResult::Unfinished { steps };
+        }
+        let jump = *offset;
+        *offset = rule.update(jump);
+        if *offset == jump {
+            if !unchanged.insert(pointer as usize) {
+                return MazeResult::Loops {
+                    steps,
+                    at: pointer as usize,
+                };
+            }
+        } else if !unchanged.is_empty() {
+            unchanged.clear();
+        }
+        pointer += jump as i64;
+        steps += 1;
+    }
+
+    let exit = if pointer < 0 { Exit::Left } else { Exit::Right };
+    MazeResult::Escaped { steps, exit }
+}
+
+fn escape_steps(maze: &[i32], rule: Rule) -> usize {
+    match run_maze(maze, rule, None) {
+        MazeResult::Escaped { steps, .. } => steps,
+        other => panic!("The maze has no way out: {:?}", other),
+    }
+}
+
+/// Cells per chunk of the settled prefix in `escape_strange`.
+const CHUNK: usize = 8;
+
+/// Walk through one settled chunk, see `chunk_table`.
+#[derive(Debug, Clone, Copy)]
+struct ChunkWalk {
+    steps: u8,
+    bits: u8,
+    exit: u8,
+}
+
+/// Every way through a chunk of 2s and 3s, indexed by the chunk's bits
+/// (1 for a 3) and the cell the pointer enters at.
+fn chunk_table() -> &'static [ChunkWalk] {
+    static TABLE: OnceLock<Vec<ChunkWalk>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=u8::MAX)
+            .flat_map(|bits| {
+                (0..CHUNK).map(move |entry| {
+                    let mut walk = ChunkWalk {
+                        steps: 0,
+                        bits,
+                        exit: entry as u8,
+                    };
+                    while (walk.exit as usize) < CHUNK {
+                        let three = (walk.bits >> walk.exit) & 1;
+                        walk.bits ^= 1 << walk.exit;
+                        walk.exit += 2 + three;
+                        walk.steps += 1;
+                    }
+                    walk
+                })
+            })
+            .collect()
+    })
+}
+
+/// Step count of `Rule::Strange`. Offsets of 2 and 3 only ever turn into
+/// each other, and the start of the maze soon consists of nothing else.
+/// That settled prefix is kept as bits and crossed a chunk at a time, the
+/// rest is stepped through one jump at a time.
+fn escape_strange(maze: &[i32]) -> usize {
+    let table = chunk_table();
+    let mut maze = maze.to_vec();
+    let mut settled: Vec<u8> = Vec::new();
+    let mut pointer: i64 = 0;
+    let mut steps = 0;
+
+    let settle = |maze: &[i32], settled: &mut Vec<u8>| {
+        while let Some(chunk) = maze.get(settled.len() * CHUNK..(settled.len() + 1) * CHUNK) {
+            if !chunk.iter().all(|&offset| offset == 2 || offset == 3) {
+                break;
+            }
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (i, &offset)| bits | ((offset as u8 - 2) << i));
+            settled.push(bits);
         }
+    };
+    settle(&maze, &mut settled);
+
+    loop {
+        if (pointer as usize) < settled.len() * CHUNK {
+            let mut entry = pointer as usize % CHUNK;
+            for bits in &mut settled[pointer as usize / CHUNK..] {
+                let walk = table[*bits as usize * CHUNK + entry];
+                *bits = walk.bits;
+                steps += walk.steps as usize;
+                entry = walk.exit as usize - CHUNK;
+            }
+            pointer = (settled.len() * CHUNK + entry) as i64;
+        }
+
+        let Some(offset) = usize::try_from(pointer).ok().and_then(|i| maze.get_mut(i)) else {
+            return steps;
+        };
+        let jump = *offset;
+        *offset = Rule::Strange.update(jump);
+        if pointer as usize / CHUNK == settled.len() {
+            settle(&maze, &mut settled);
+        }
+        pointer += jump as i64;
+        steps += 1;
     }
-    counter
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+fn parse_input(s: &str) -> ParseResult<Program> {
+    parse::words(Span::new(s))
+}
+
+fn solve_part1(input: &Program) -> usize {
+    escape_steps(input, Rule::Increment)
+}
+
+fn solve_part2(input: &Program) -> usize {
+    escape_strange(input)
+}
+
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(5))
+    let (sol1, sol2) = solve_str(helpers::read_day(5)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -52,13 +218,62 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("05"));
+        let input = parse_input(helpers::read_example("05")).unwrap();
         assert_eq!(solve_part1(&input), 5);
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("05"));
+        let input = parse_input(helpers::read_example("05")).unwrap();
         assert_eq!(solve_part2(&input), 10);
     }
+
+    #[test]
+    fn test_run_maze() {
+        let escaped = |steps, exit| MazeResult::Escaped { steps, exit };
+        assert_eq!(
+            run_maze(&[0, 3, 0, 1, -3], Rule::Increment, None),
+            escaped(5, Exit::Right)
+        );
+        assert_eq!(
+            run_maze(&[1, -2], Rule::Increment, None),
+            escaped(2, Exit::Left)
+        );
+        assert_eq!(run_maze(&[], Rule::Strange, None), escaped(0, Exit::Right));
+
+        let stay = |offset| offset;
+        assert_eq!(
+            run_maze(&[1, 0, 5], stay, None),
+            MazeResult::Loops { steps: 2, at: 1 }
+        );
+        assert_eq!(
+            run_maze(&[1, 1, -1], stay, None),
+            MazeResult::Loops { steps: 3, at: 1 }
+        );
+        assert_eq!(
+            run_maze(&[0, 3, 0, 1, -3], Rule::Increment, Some(4)),
+            MazeResult::Unfinished { steps: 4 }
+        );
+    }
+
+    #[test]
+    fn test_escape_strange() {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut xorshift = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..200 {
+            let len = (xorshift() % 300) as usize;
+            let maze: Vec<i32> = (0..len).map(|_| (xorshift() % 11) as i32 - 5).collect();
+            let naive = run_maze(&maze, Rule::Strange, Some(10_000_000));
+            let MazeResult::Escaped { steps, .. } = naive else {
+                panic!("{:?} for {:?}", naive, maze);
+            };
+            assert_eq!(escape_strange(&maze), steps, "{:?}", maze);
+        }
+    }
 }
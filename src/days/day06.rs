@@ -1,68 +1,187 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::str::FromStr;
+
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
-
-use std::collections::hash_map::{
-    Entry::{Occupied, Vacant},
-    HashMap,
-};
-
-type Memory = Vec<u8>;
-type Visited = HashMap<Memory, usize>;
-
-fn parse_input(s: DayString) -> Memory {
-    let mut mem: Memory = s
-        .trim()
-        .split(char::is_whitespace)
-        .flat_map(str::parse)
-        .collect();
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
+use crate::SolveError;
+
+/// Unsigned types a bank can count its blocks in.
+pub trait Blocks: Copy + Ord + Hash + Debug + Display + FromStr {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn to_u64(self) -> u64;
+    fn from_u64(n: u64) -> Option<Self>;
+}
+
+macro_rules! impl_blocks {
+    ($($t:ty),*) => {
+        $(
+            impl Blocks for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_u64(n: u64) -> Option<Self> {
+                    n.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_blocks!(u8, u16, u32, u64);
+
+/// A bank got more blocks than its type can count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a memory bank overflowed")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+type Memory = Vec<u32>;
+
+fn parse_input(s: &str) -> ParseResult<Memory> {
+    let mut mem: Memory = parse::words(Span::new(s))?;
     mem.shrink_to_fit();
-    mem
+    Ok(mem)
 }
 
-fn redistribute(mem: &mut Memory) {
-    let max_ind = mem
-        .iter()
-        .copied()
-        .enumerate()
-        .max_by_key(|&(i, n)| (n, -(i as i8)))
-        .unwrap()
-        .0;
+/// Empties the fullest bank, the first one on ties, and deals its blocks
+/// out to the banks after it. On overflow the banks are left half dealt.
+pub fn redistribute<B: Blocks>(mem: &mut [B]) -> Result<(), Overflow> {
+    let Some(&max) = mem.iter().max() else {
+        return Ok(());
+    };
+    let max_ind = mem.iter().position(|&b| b == max).unwrap();
     let mem_len = mem.len();
-    let n = mem_len as u8;
-    let k = mem[max_ind];
+    let n = mem_len as u64;
+    let k = max.to_u64();
 
-    let add = k / n;
+    let add = B::from_u64(k / n).ok_or(Overflow)?;
     let rem = (k % n) as usize;
 
-    mem[max_ind] = 0;
+    mem[max_ind] = B::ZERO;
 
-    (0..mem.len()).for_each(|i| mem[i] += add);
-    (max_ind + 1..max_ind + 1 + rem).for_each(|i| mem[i % mem_len] += 1);
+    for bank in mem.iter_mut() {
+        *bank = bank.checked_add(add).ok_or(Overflow)?;
+    }
+    for i in max_ind + 1..max_ind + 1 + rem {
+        let bank = &mut mem[i % mem_len];
+        *bank = bank.checked_add(B::ONE).ok_or(Overflow)?;
+    }
+    Ok(())
 }
 
-fn solve_day(input: &Memory) -> (usize, usize) {
-    let mut visited = Visited::new();
-    let mut mem = input.clone();
+/// Redistributions until a state comes up again, and how many steps
+/// before that the state was first seen.
+pub fn cycle<B: Blocks>(banks: &[B]) -> Result<(usize, usize), Overflow> {
+    let mut visited = HashMap::new();
+    let mut mem = banks.to_vec();
     let mut counter = 0;
     let time = loop {
-        match visited.entry(mem.clone()) {
-            Vacant(entry) => entry.insert(counter),
-            Occupied(entry) => break *entry.get(),
-        };
-        redistribute(&mut mem);
+        if let Some(&time) = visited.get(&mem) {
+            break time;
+        }
+        visited.insert(mem.clone(), counter);
+        redistribute(&mut mem)?;
         counter += 1;
+        crate::trace!(Debug, counter, "banks {:?}", mem);
     };
-    (counter, counter - time)
+    Ok((counter, counter - time))
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let (sol1, sol2) = solve_day(&parsed);
-    [sol1.to_string(), sol2.to_string()]
+/// Same as `cycle`, but with Brent's algorithm, which keeps no more than
+/// two states around at any time instead of all of them.
+pub fn cycle_brent<B: Blocks>(banks: &[B]) -> Result<(usize, usize), Overflow> {
+    let step = |mem: &mut Vec<B>| redistribute(mem);
+
+    // Find the loop length, the hare racing ahead of a tortoise that
+    // teleports to it at every power of two.
+    let mut tortoise = banks.to_vec();
+    let mut hare = banks.to_vec();
+    step(&mut hare)?;
+    let (mut power, mut length) = (1, 1);
+    let mut steps = 1;
+    while tortoise != hare {
+        if power == length {
+            tortoise.clone_from(&hare);
+            power *= 2;
+            length = 0;
+        }
+        step(&mut hare)?;
+        length += 1;
+        steps += 1;
+        crate::trace!(Debug, steps, "banks {:?}", hare);
+    }
+
+    // Find where the loop starts, with the hare a loop length ahead.
+    tortoise.clear();
+    tortoise.extend_from_slice(banks);
+    hare.clone_from(&tortoise);
+    for _ in 0..length {
+        step(&mut hare)?;
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        step(&mut tortoise)?;
+        step(&mut hare)?;
+        start += 1;
+    }
+    Ok((start + length, length))
+}
+
+/// Writes every state up to and including the first repeated one as CSV,
+/// one row per step. Returns the number of steps.
+pub fn export_states<B: Blocks>(banks: &[B], mut out: impl Write) -> io::Result<usize> {
+    let (steps, _) = cycle_brent(banks).map_err(io::Error::other)?;
+    let header: Vec<String> = (0..banks.len()).map(|i| format!("bank{}", i)).collect();
+    writeln!(out, "step,{}", header.join(","))?;
+
+    let mut mem = banks.to_vec();
+    for step in 0..=steps {
+        let row: Vec<String> = mem.iter().map(|b| b.to_string()).collect();
+        writeln!(out, "{},{}", step, row.join(","))?;
+        redistribute(&mut mem).map_err(io::Error::other)?;
+    }
+    Ok(steps)
+}
+
+/// The banks in `s` through their states, see `export_states`.
+pub fn export_str(s: &str) -> Result<String, SolveError> {
+    let banks = parse_input(s)?;
+    let mut out = Vec::new();
+    export_states(&banks, &mut out).map_err(|e| SolveError::Solve(e.to_string()))?;
+    Ok(String::from_utf8(out).expect("CSV should be UTF-8"))
+}
+
+pub fn solve_str(s: &str) -> Result<(usize, usize), SolveError> {
+    let parsed = parse_input(s)?;
+    let (sol1, sol2) =
+        helpers::timed(|| cycle_brent(&parsed)).map_err(|e| SolveError::Solve(e.to_string()))?;
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(6))
+    let (sol1, sol2) = solve_str(helpers::read_day(6)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -71,13 +190,58 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("06"));
-        assert_eq!(solve_day(&input).0, 5);
+        let input = parse_input(helpers::read_example("06")).unwrap();
+        assert_eq!(cycle(&input).unwrap().0, 5);
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("06"));
-        assert_eq!(solve_day(&input).1, 4);
+        let input = parse_input(helpers::read_example("06")).unwrap();
+        assert_eq!(cycle(&input).unwrap().1, 4);
+    }
+
+    #[test]
+    fn test_redistribute() {
+        let mut banks = vec![3u8, 200, 40];
+        redistribute(&mut banks).unwrap();
+        assert_eq!(banks, [70, 66, 107]);
+
+        let mut banks = vec![0u8; 300];
+        banks[299] = 255;
+        redistribute(&mut banks).unwrap();
+        assert_eq!(banks.iter().map(|&b| b as u32).sum::<u32>(), 255);
+        assert_eq!((banks[0], banks[254], banks[255], banks[299]), (1, 1, 0, 0));
+
+        let mut banks = vec![255u8, 255];
+        assert_eq!(redistribute(&mut banks), Err(Overflow));
+
+        assert_eq!(cycle(&[0u64, 2, 7, 0]), Ok((5, 4)));
+        let max = u32::MAX;
+        assert_eq!(
+            solve_str(&format!("{} {}", max, max)),
+            Err(SolveError::Solve(Overflow.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_brent() {
+        let input = parse_input(helpers::read_example("06")).unwrap();
+        assert_eq!(cycle_brent(&input), Ok((5, 4)));
+
+        for banks in [vec![1u16; 200], vec![9, 0, 0, 4, 500, 3], vec![7], vec![]] {
+            assert_eq!(cycle_brent(&banks), cycle(&banks), "{:?}", banks);
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let mut csv = Vec::new();
+        assert_eq!(export_states(&[0u8, 2, 7, 0], &mut csv).unwrap(), 5);
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 7);
+        assert_eq!(rows[0], "step,bank0,bank1,bank2,bank3");
+        assert_eq!(rows[1], "0,0,2,7,0");
+        assert_eq!(rows[6], "5,2,4,1,2");
     }
 }
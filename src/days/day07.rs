@@ -1,126 +1,557 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseError, ParseResult, Span};
+use crate::helpers::Solution;
+use crate::SolveError;
 
-use regex::Regex;
-use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
 
-type Name = &'static str;
+type Name<'a> = &'a str;
+
+/// Levels of the tower `report_str` shows above the root.
+const REPORT_DEPTH: usize = 2;
 
 #[derive(Debug)]
-struct Disk {
-    name: Name,
+struct Disk<'a> {
+    name: Name<'a>,
     weight: u16,
-    above: Vec<Disk>,
+    /// Own weight plus the weight of everything above.
+    total: usize,
+    above: Vec<Disk<'a>>,
+}
+
+impl Drop for Disk<'_> {
+    /// Takes deep towers apart without recursing.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.above);
+        while let Some(mut disk) = stack.pop() {
+            stack.append(&mut disk.above);
+        }
+    }
 }
 
 #[derive(Debug)]
-struct Tower {
-    root: Disk,
+pub struct Tower<'a> {
+    root: Disk<'a>,
+}
+
+/// A program with its own weight and the weight of everything it holds up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weighed<'a> {
+    pub name: Name<'a>,
+    pub weight: usize,
+    pub total: usize,
+}
+
+/// The programs one program holds up, on the way to the unbalanced one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Level<'a> {
+    pub parent: Name<'a>,
+    pub children: Vec<Weighed<'a>>,
+    /// Index of the child that weighs differently from its siblings.
+    pub odd: usize,
+}
+
+/// Where the tower is out of balance and how to fix it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis<'a> {
+    /// From the root up to the unbalanced program.
+    pub levels: Vec<Level<'a>>,
+    /// Total weight of each of the unbalanced program's siblings.
+    pub majority: usize,
+    /// Weight the unbalanced program should have.
+    pub corrected: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImbalanceError<'a> {
+    Balanced,
+    /// Two programs weigh differently and nothing tells which is wrong.
+    Ambiguous {
+        parent: Name<'a>,
+        children: [Weighed<'a>; 2],
+    },
+    /// More than one program weighs differently from its siblings.
+    SeveralOdd {
+        parent: Name<'a>,
+        odd: Vec<Name<'a>>,
+    },
+    /// Only a negative weight would balance the tower.
+    NegativeWeight {
+        name: Name<'a>,
+    },
+}
+
+/// A line of the input, before the tower is put together.
+struct Record<'a> {
+    line: Span<'a>,
+    weight: u16,
+    above: Vec<Span<'a>>,
 }
 
-fn parse_input(s: DayString) -> Tower {
-    fn initialise_disk(
-        name: Name,
-        un_disks: &mut HashMap<Name, (u16, Vec<Name>)>,
-        fin_disks: &mut HashMap<Name, Disk>,
-    ) -> Option<Disk> {
-        if let Entry::Occupied(entry) = fin_disks.entry(name) {
-            return Some(entry.remove());
+impl<'a> Tower<'a> {
+    /// Checks that the programs form a single tree: every program held up
+    /// exists and has one program below it, exactly one program stands on
+    /// nothing, and nothing holds itself up.
+    fn build(mut records: HashMap<Name<'a>, Record<'a>>, order: &[Name<'a>]) -> ParseResult<Self> {
+        let mut below: HashMap<Name, Name> = HashMap::new();
+        for &name in order {
+            for child in &records[name].above {
+                let child_name = child.as_str();
+                if !records.contains_key(child_name) {
+                    return Err(child.error(format!(
+                        "`{}` holds up unknown program `{}`",
+                        name, child_name
+                    )));
+                }
+                if let Some(other) = below.insert(child_name, name) {
+                    return Err(child.error(format!(
+                        "`{}` is held up by both `{}` and `{}`",
+                        child_name, other, name
+                    )));
+                }
+            }
         }
 
-        if let Entry::Occupied(entry) = un_disks.entry(name) {
-            let (weight, children) = entry.remove();
-            let above = children
+        let roots: Vec<Name> = order
+            .iter()
+            .copied()
+            .filter(|name| !below.contains_key(name))
+            .collect();
+        let root = match roots[..] {
+            [root] => root,
+            [] if order.is_empty() => return Err(Span::new("").error("no programs")),
+            [] => return Err(Tower::cycle_error(&records, &below, order[0])),
+            [_, second, ..] => {
+                return Err(records[second].line.error(format!(
+                    "several programs stand on nothing: `{}`",
+                    roots.join("`, `")
+                )))
+            }
+        };
+
+        let mut reached = 0;
+        let mut stack = vec![root];
+        while let Some(name) = stack.pop() {
+            reached += 1;
+            stack.extend(records[name].above.iter().map(|child| child.as_str()));
+        }
+        if reached < order.len() {
+            let stray = order
                 .iter()
-                .filter_map(|child| initialise_disk(child, un_disks, fin_disks))
-                .collect();
-            return Some(Disk {
+                .find(|&&name| !Tower::stands_on(&below, name, root))
+                .unwrap();
+            return Err(Tower::cycle_error(&records, &below, stray));
+        }
+
+        Ok(Tower {
+            root: Tower::build_disk(root, &mut records),
+        })
+    }
+
+    fn stands_on(below: &HashMap<Name<'a>, Name<'a>>, mut name: Name<'a>, root: Name) -> bool {
+        for _ in 0..=below.len() {
+            if name == root {
+                return true;
+            }
+            match below.get(name) {
+                Some(&next) => name = next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Follows the programs below `start` until one comes up twice.
+    fn cycle_error(
+        records: &HashMap<Name, Record>,
+        below: &HashMap<Name, Name>,
+        start: Name,
+    ) -> ParseError {
+        let mut seen = vec![start];
+        let mut name = start;
+        while let Some(&next) = below.get(name) {
+            if let Some(i) = seen.iter().position(|&n| n == next) {
+                let mut cycle = seen[i..].to_vec();
+                cycle.push(next);
+                return records[next].line.error(format!(
+                    "programs hold each other up: `{}`",
+                    cycle.join("` -> `")
+                ));
+            }
+            seen.push(next);
+            name = next;
+        }
+        records[start]
+            .line
+            .error(format!("`{}` is not part of the tower", start))
+    }
+
+    /// Builds the disks from `root` up without recursing, so generated
+    /// towers can be as tall as they like.
+    fn build_disk(root: Name<'a>, records: &mut HashMap<Name<'a>, Record<'a>>) -> Disk<'a> {
+        // The programs on the way up from the root, each with the disks
+        // built so far for the programs it holds up.
+        let mut stack = vec![(root, records.remove(root).unwrap(), Vec::new())];
+        loop {
+            let (_, record, above) = stack.last().unwrap();
+            if let Some(child) = record.above.get(above.len()) {
+                let name = child.as_str();
+                stack.push((name, records.remove(name).unwrap(), Vec::new()));
+                continue;
+            }
+            let (name, record, above) = stack.pop().unwrap();
+            let disk = Disk {
                 name,
-                weight,
+                weight: record.weight,
+                total: record.weight as usize + above.iter().map(|d: &Disk| d.total).sum::<usize>(),
                 above,
-            });
+            };
+            match stack.last_mut() {
+                Some((_, _, parent)) => parent.push(disk),
+                None => return disk,
+            }
         }
+    }
 
-        None
+    pub fn parse(s: &'a str) -> ParseResult<Self> {
+        parse_input(s)
     }
 
-    let re = Regex::new(
-        r"(?m)^(?P<name>\w+) \(:?(?P<weight>\d+)\)(:? -> (?P<children>(:?(:?\w+(, )?))*))?$",
-    )
-    .unwrap();
+    /// Climbs from the root towards the child that weighs differently from
+    /// its siblings, until reaching one whose own children are balanced.
+    pub fn diagnose(&self) -> Result<Diagnosis<'a>, ImbalanceError<'a>> {
+        let mut levels = Vec::new();
+        let mut disk = &self.root;
+        let mut majority = None;
 
-    let mut names = Vec::new();
-    let mut uninitialized_disks = HashMap::new();
+        loop {
+            let children: Vec<Weighed> = disk.above.iter().map(Disk::weighed).collect();
+            match odd_one_out(disk.name, &children)? {
+                Some((odd, total)) => {
+                    levels.push(Level {
+                        parent: disk.name,
+                        children,
+                        odd,
+                    });
+                    majority = Some(total);
+                    disk = &disk.above[odd];
+                }
+                None => {
+                    let majority = majority.ok_or(ImbalanceError::Balanced)?;
+                    let corrected = (disk.weight as usize + majority)
+                        .checked_sub(disk.total)
+                        .ok_or(ImbalanceError::NegativeWeight { name: disk.name })?;
+                    return Ok(Diagnosis {
+                        levels,
+                        majority,
+                        corrected,
+                    });
+                }
+            }
+        }
+    }
+}
 
-    for caps in re.captures_iter(s) {
-        let name = caps.name("name").unwrap().as_str();
-        let weight: u16 = caps["weight"].parse().unwrap();
-        let children: Vec<Name> = caps
-            .name("children")
-            .map(|mat| mat.as_str().split(", ").collect())
-            .unwrap_or_default();
+impl<'a> Disk<'a> {
+    fn weighed(&self) -> Weighed<'a> {
+        Weighed {
+            name: self.name,
+            weight: self.weight as usize,
+            total: self.total,
+        }
+    }
+}
 
-        names.push(name);
-        uninitialized_disks.insert(name, (weight, children));
+impl Tower<'_> {
+    /// The unbalanced program, whose subtree the exports highlight.
+    fn culprit(&self) -> Option<Name<'_>> {
+        self.diagnose().ok().map(|d| d.culprit().name)
     }
 
-    let mut initialized_disks = HashMap::new();
+    /// Indented tree with own and total weights, showing at most `depth`
+    /// levels above the root. The unbalanced program is marked with `!`.
+    pub fn to_text(&self, depth: Option<usize>) -> String {
+        let mut out = String::new();
+        self.root.write_text(&mut out, depth, self.culprit());
+        out.truncate(out.trim_end().len());
+        out
+    }
 
-    for name in names {
-        if let Some(disk) = initialise_disk(name, &mut uninitialized_disks, &mut initialized_disks)
-        {
-            initialized_disks.insert(name, disk);
-        };
+    /// Graphviz graph with the root at the bottom and the unbalanced
+    /// program and everything it holds up filled in red.
+    pub fn to_dot(&self) -> String {
+        let mut out = "digraph tower {\n    rankdir=BT;\n    node [shape=box];\n".to_string();
+        self.root.write_dot(&mut out, self.culprit());
+        out.push('}');
+        out
+    }
+}
+
+impl Disk<'_> {
+    fn write_text(&self, out: &mut String, depth: Option<usize>, culprit: Option<Name>) {
+        // Disks still to write, with the lead of their own line and the
+        // prefix of the lines above them, and how many more levels to show.
+        let mut stack = vec![(self, String::new(), String::new(), depth)];
+        while let Some((disk, lead, prefix, depth)) = stack.pop() {
+            let _ = write!(
+                out,
+                "{}{} ({}) total {}",
+                lead, disk.name, disk.weight, disk.total
+            );
+            if Some(disk.name) == culprit {
+                out.push_str(" !");
+            }
+            if depth == Some(0) && !disk.above.is_empty() {
+                let _ = write!(out, " [{} more above]", disk.above.len());
+            }
+            out.push('\n');
+            if depth == Some(0) {
+                continue;
+            }
+            for (i, above) in disk.above.iter().enumerate().rev() {
+                let (branch, indent) = match i + 1 == disk.above.len() {
+                    true => ("`-- ", "    "),
+                    false => ("|-- ", "|   "),
+                };
+                stack.push((
+                    above,
+                    format!("{}{}", prefix, branch),
+                    format!("{}{}", prefix, indent),
+                    depth.map(|d| d - 1),
+                ));
+            }
+        }
     }
 
-    if let Some(root) = initialized_disks.into_values().next() {
-        return Tower { root };
+    fn write_dot(&self, out: &mut String, culprit: Option<Name>) {
+        // Disks still to write, with the id of the disk below and whether
+        // they stand on the culprit.
+        let mut stack = vec![(self, None, false)];
+        while let Some((disk, below, highlight)) = stack.pop() {
+            let highlight = highlight || Some(disk.name) == culprit;
+            let id = dot_string(disk.name);
+            if let Some(below) = below {
+                let _ = writeln!(out, "    {} -> {};", below, id);
+            }
+            let _ = write!(
+                out,
+                "    {} [label=\"{}\\n{} / {}\"",
+                id,
+                &id[1..id.len() - 1],
+                disk.weight,
+                disk.total
+            );
+            if highlight {
+                out.push_str(", style=filled, fillcolor=\"#f4a6a6\"");
+            }
+            out.push_str("];\n");
+            for above in disk.above.iter().rev() {
+                stack.push((above, Some(id.clone()), highlight));
+            }
+        }
     }
+}
 
-    panic!("Invalid input, no root found")
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn solve_part1(input: &Tower) -> &str {
-    input.root.name
+/// Index of the only child whose total differs from the others', with the
+/// others' total. `None` if all children weigh the same.
+fn odd_one_out<'a>(
+    parent: Name<'a>,
+    children: &[Weighed<'a>],
+) -> Result<Option<(usize, usize)>, ImbalanceError<'a>> {
+    let Some(first) = children.first() else {
+        return Ok(None);
+    };
+    if children.iter().all(|c| c.total == first.total) {
+        return Ok(None);
+    }
+    if let [a, b] = children {
+        return Err(ImbalanceError::Ambiguous {
+            parent,
+            children: [*a, *b],
+        });
+    }
+
+    let count = |total| children.iter().filter(|c| c.total == total).count();
+    let majority = children
+        .iter()
+        .map(|c| c.total)
+        .find(|&total| count(total) > 1)
+        .unwrap_or(first.total);
+    let odd: Vec<usize> = (0..children.len())
+        .filter(|&i| children[i].total != majority)
+        .collect();
+    match odd[..] {
+        [odd] if count(majority) > 1 => Ok(Some((odd, majority))),
+        _ => Err(ImbalanceError::SeveralOdd {
+            parent,
+            odd: odd.iter().map(|&i| children[i].name).collect(),
+        }),
+    }
 }
 
-fn solve_part2(input: &Tower) -> usize {
-    fn find_unbalanced(disk: &Disk) -> Result<usize, usize> {
-        let results: Result<Vec<usize>, usize> = disk.above.iter().map(find_unbalanced).collect();
-        let results: Vec<usize> = results?;
+impl<'a> Diagnosis<'a> {
+    /// Names from the root up to the unbalanced program.
+    pub fn path(&self) -> Vec<Name<'a>> {
+        let root = self.levels.first().map(|level| level.parent);
+        root.into_iter()
+            .chain(
+                self.levels
+                    .iter()
+                    .map(|level| level.children[level.odd].name),
+            )
+            .collect()
+    }
 
-        let maj_weight: usize = results.get(1).copied().unwrap_or_default();
+    /// The unbalanced program and its siblings.
+    pub fn siblings(&self) -> &[Weighed<'a>] {
+        &self.levels.last().unwrap().children
+    }
 
-        for (i, &weight) in results.iter().enumerate() {
-            if weight != maj_weight {
-                let maj_weight = if i > 1 {
-                    results[i - 1]
-                } else {
-                    results[i + 1]
-                };
-                return Err((disk.above[i].weight as usize + maj_weight) - weight);
+    pub fn culprit(&self) -> Weighed<'a> {
+        let level = self.levels.last().unwrap();
+        level.children[level.odd]
+    }
+
+    /// How much the unbalanced program's weight has to change.
+    pub fn correction(&self) -> i64 {
+        self.corrected as i64 - self.culprit().weight as i64
+    }
+
+    /// The children of every level, each level's below its odd one out.
+    fn fmt_levels(f: &mut fmt::Formatter<'_>, levels: &[Level]) -> fmt::Result {
+        // Levels being written, with the next child to write.
+        let mut stack = vec![(0, 0)];
+        while let Some((depth, i)) = stack.pop() {
+            let Some(child) = levels.get(depth).and_then(|level| level.children.get(i)) else {
+                continue;
+            };
+            write!(
+                f,
+                "{:indent$}{} ({}) total {}",
+                "",
+                child.name,
+                child.weight,
+                child.total,
+                indent = 2 * (depth + 1)
+            )?;
+            stack.push((depth, i + 1));
+            if i == levels[depth].odd {
+                writeln!(f, "  <- odd one out")?;
+                stack.push((depth + 1, 0));
+            } else {
+                writeln!(f)?;
             }
         }
+        Ok(())
+    }
+}
 
-        Ok(disk.above.len() * maj_weight + disk.weight as usize)
+impl fmt::Display for Diagnosis<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.levels[0].parent)?;
+        Diagnosis::fmt_levels(f, &self.levels)?;
+        let culprit = self.culprit();
+        write!(
+            f,
+            "`{}` weighs {} but should weigh {} ({:+}) for a total of {}",
+            culprit.name,
+            culprit.weight,
+            self.corrected,
+            self.correction(),
+            self.majority
+        )
     }
+}
 
-    if let Err(n) = find_unbalanced(&input.root) {
-        return n;
+impl fmt::Display for ImbalanceError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImbalanceError::Balanced => write!(f, "the tower is balanced"),
+            ImbalanceError::Ambiguous {
+                parent,
+                children: [a, b],
+            } => write!(
+                f,
+                "`{}` holds up `{}` ({}) and `{}` ({}), either could be wrong",
+                parent, a.name, a.total, b.name, b.total
+            ),
+            ImbalanceError::SeveralOdd { parent, odd } => write!(
+                f,
+                "several programs on `{}` are out of balance: `{}`",
+                parent,
+                odd.join("`, `")
+            ),
+            ImbalanceError::NegativeWeight { name } => {
+                write!(f, "`{}` would need a negative weight", name)
+            }
+        }
     }
+}
 
-    panic!("No unbalancedness found!")
+fn parse_input(s: &str) -> ParseResult<Tower<'_>> {
+    let mut order = Vec::new();
+    let mut records = HashMap::new();
+
+    for line in parse::lines(s) {
+        let (disk, above) = match line.split_once("->") {
+            Ok((disk, above)) => (disk, above.split(",").collect()),
+            Err(_) => (line, Vec::new()),
+        };
+        let (name, weight) = disk.trim().split_once(" (")?;
+        let weight: u16 = weight.strip_suffix(")")?.parse()?;
+        let name = name.as_str();
+
+        let record = Record {
+            line,
+            weight,
+            above,
+        };
+        if records.insert(name, record).is_some() {
+            return Err(line.error(format!("program `{}` is listed twice", name)));
+        }
+        order.push(name);
+    }
+    Tower::build(records, &order)
+}
+
+fn solve_part1<'a>(input: &Tower<'a>) -> &'a str {
+    input.root.name
+}
+
+fn solve_part2<'a>(input: &Tower<'a>) -> Result<usize, ImbalanceError<'a>> {
+    input.diagnose().map(|diagnosis| diagnosis.corrected)
+}
+
+pub fn solve_str(s: &str) -> Result<(&str, usize), SolveError> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 =
+        helpers::timed(|| solve_part2(&parsed)).map_err(|e| SolveError::Solve(e.to_string()))?;
+    Ok((sol1, sol2))
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+/// How the tower is out of balance, then its lower levels.
+pub fn report_str(s: &str) -> ParseResult<String> {
+    let tower = parse_input(s)?;
+    let diagnosis = match tower.diagnose() {
+        Ok(diagnosis) => diagnosis.to_string(),
+        Err(e) => format!("No diagnosis: {}", e),
+    };
+    Ok(format!(
+        "{}\n\n{}",
+        diagnosis,
+        tower.to_text(Some(REPORT_DEPTH))
+    ))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(7))
+    let (sol1, sol2) = solve_str(helpers::read_day(7)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -129,18 +560,135 @@ mod tests {
 
     #[test]
     fn test_build() {
-        let _input = parse_input(helpers::read_example("07"));
+        let _input = parse_input(helpers::read_example("07")).unwrap();
     }
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("07"));
+        let input = parse_input(helpers::read_example("07")).unwrap();
         assert_eq!(solve_part1(&input), "tknk");
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("07"));
-        assert_eq!(solve_part2(&input), 60);
+        let input = parse_input(helpers::read_example("07")).unwrap();
+        assert_eq!(solve_part2(&input), Ok(60));
+
+        let err = solve_str("r (1) -> a, b\na (1)\nb (1)").unwrap_err();
+        assert_eq!(err, SolveError::Solve("the tower is balanced".to_string()));
+        let err = solve_str("r (1) -> a, b\na (1)\nb (2)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no answer: `r` holds up `a` (1) and `b` (2), either could be wrong"
+        );
+        assert!(crate::solve_str(7, "r (1) -> a, b\na (1)\nb (2)").is_err());
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let error = |s| parse_input(s).unwrap_err().msg;
+        assert_eq!(
+            error("a (1) -> b\nc (2)"),
+            "`a` holds up unknown program `b`"
+        );
+        assert_eq!(
+            error("a (1) -> c\nb (1) -> c\nc (2)\nr (3) -> a, b"),
+            "`c` is held up by both `a` and `b`"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1)\nc (2)"),
+            "several programs stand on nothing: `a`, `c`"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1) -> a"),
+            "programs hold each other up: `a` -> `b` -> `a`"
+        );
+        assert_eq!(
+            error("r (1) -> a\na (1)\nb (1) -> c\nc (1) -> b"),
+            "programs hold each other up: `b` -> `c` -> `b`"
+        );
+        assert_eq!(error("a (1)\na (2)"), "program `a` is listed twice");
+        assert_eq!(parse_input("a (1) -> b").unwrap_err().column, 10);
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let input = parse_input(helpers::read_example("07")).unwrap();
+        let diagnosis = input.diagnose().unwrap();
+        assert_eq!(diagnosis.path(), ["tknk", "ugml"]);
+        assert_eq!(diagnosis.majority, 243);
+        assert_eq!(diagnosis.correction(), -8);
+        let totals: Vec<usize> = diagnosis.siblings().iter().map(|c| c.total).collect();
+        assert_eq!(totals, [251, 243, 243]);
+        assert_eq!(
+            diagnosis.to_string(),
+            "tknk\n  ugml (68) total 251  <- odd one out\n  padx (45) total 243\n  \
+             fwft (72) total 243\n`ugml` weighs 68 but should weigh 60 (-8) for a total of 243"
+        );
+
+        // The odd one first, and deeper down.
+        let input =
+            parse_input("r (1) -> a, b, c\na (5) -> d, e, f\nb (8)\nc (8)\nd (1)\ne (2)\nf (1)")
+                .unwrap();
+        let diagnosis = input.diagnose().unwrap();
+        assert_eq!(diagnosis.path(), ["r", "a", "e"]);
+        assert_eq!(diagnosis.corrected, 1);
+
+        let error = |s| parse_input(s).unwrap().diagnose().unwrap_err();
+        assert_eq!(
+            error("r (1) -> a, b\na (1)\nb (1)"),
+            ImbalanceError::Balanced
+        );
+        assert!(matches!(
+            error("r (1) -> a, b\na (1)\nb (2)"),
+            ImbalanceError::Ambiguous { .. }
+        ));
+        assert!(matches!(
+            error("r (1) -> a, b, c\na (1)\nb (2)\nc (3)"),
+            ImbalanceError::SeveralOdd { .. }
+        ));
+    }
+
+    #[test]
+    fn test_export() {
+        let input = parse_input(helpers::read_example("07")).unwrap();
+        assert_eq!(
+            input.to_text(Some(1)),
+            "tknk (41) total 778\n\
+             |-- ugml (68) total 251 ! [3 more above]\n\
+             |-- padx (45) total 243 [3 more above]\n\
+             `-- fwft (72) total 243 [3 more above]"
+        );
+        let text = input.to_text(None);
+        assert_eq!(text.lines().count(), 13);
+        assert!(text.contains("|   `-- jptl (61) total 61\n"));
+
+        let dot = input.to_dot();
+        assert!(dot.starts_with("digraph tower {"));
+        assert!(dot.contains("\"tknk\" -> \"ugml\";"));
+        assert!(dot.contains("\"gyxo\" [label=\"gyxo\\n61 / 61\", style=filled"));
+        assert!(dot.contains("\"pbga\" [label=\"pbga\\n66 / 66\"];"));
+        assert_eq!(dot_string("a\"b"), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_large() {
+        // `c0` holds up a chain of `n` programs of weight 1 and weighs one
+        // too much, its siblings are short chains of heavy programs.
+        let n = 200_000;
+        let mut input = "r (1) -> a0, b0, c0\n".to_string();
+        for chain in ["a", "b"] {
+            input += &format!("{0}0 (50000) -> {0}1\n{0}1 (50000) -> {0}2\n", chain);
+            input += &format!("{0}2 (50000) -> {0}3\n{0}3 (50000)\n", chain);
+        }
+        input += "c0 (2) -> c1\n";
+        for i in 1..n - 1 {
+            input += &format!("c{} (1) -> c{}\n", i, i + 1);
+        }
+        input += &format!("c{} (1)", n - 1);
+
+        assert_eq!(solve_str(&input), Ok(("r", 1)));
+        let dot = Tower::parse(&input).unwrap().to_dot();
+        assert_eq!(dot.matches(" -> ").count(), n + 8);
     }
 }
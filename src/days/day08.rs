@@ -1,23 +1,28 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 
 use std::collections::hash_map::HashMap;
+use std::fmt::Write;
 use std::str::FromStr;
 
-use regex::Regex;
+type Reg<'a> = &'a str;
+/// Index of a register in `Memory`, given out at parse time.
+type Slot = usize;
+type Value = i32;
 
-type Reg = &'static str;
-type Program = Vec<Instruction>;
-type MemTable = HashMap<Reg, i32>;
-
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operation {
     Inc,
     Dec,
+    Set,
+    Mul,
+    Div,
+    Mod,
 }
 
-#[derive(Clone, Copy)]
-enum Cond {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
     Eq,
     Leq,
     Lt,
@@ -33,118 +38,446 @@ impl FromStr for Operation {
         match s {
             "inc" => Ok(Operation::Inc),
             "dec" => Ok(Operation::Dec),
+            "set" => Ok(Operation::Set),
+            "mul" => Ok(Operation::Mul),
+            "div" => Ok(Operation::Div),
+            "mod" => Ok(Operation::Mod),
             _ => Err(()),
         }
     }
 }
 
-impl FromStr for Cond {
+impl FromStr for Cmp {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "==" => Ok(Cond::Eq),
-            "<=" => Ok(Cond::Leq),
-            "<" => Ok(Cond::Lt),
-            ">=" => Ok(Cond::Geq),
-            ">" => Ok(Cond::Gt),
-            "!=" => Ok(Cond::Neq),
+            "==" => Ok(Cmp::Eq),
+            "<=" => Ok(Cmp::Leq),
+            "<" => Ok(Cmp::Lt),
+            ">=" => Ok(Cmp::Geq),
+            ">" => Ok(Cmp::Gt),
+            "!=" => Ok(Cmp::Neq),
             _ => Err(()),
         }
     }
 }
 
-struct Instruction {
-    target: Reg,
+impl Cmp {
+    fn holds(self, a: Value, b: Value) -> bool {
+        match self {
+            Cmp::Eq => a == b,
+            Cmp::Leq => a <= b,
+            Cmp::Lt => a < b,
+            Cmp::Gt => a > b,
+            Cmp::Geq => a >= b,
+            Cmp::Neq => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Reg(Slot),
+    Lit(Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cond {
+    Compare(Operand, Cmp, Operand),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+#[derive(Debug)]
+struct Instruction<'a> {
+    line: Span<'a>,
+    /// 1-based number of `line`, counted once while parsing.
+    number: usize,
+    target: Slot,
     op: Operation,
-    by: i32,
-    cond: Cond,
-    cmp_targ: Reg,
-    cmp_val: i32,
+    by: Operand,
+    /// Instructions without `if` always run.
+    cond: Option<Cond>,
 }
 
-struct Memory {
-    table: MemTable,
+/// Register names and the slots they were given, in order of appearance.
+#[derive(Debug, Default)]
+struct Registers<'a> {
+    slots: HashMap<Reg<'a>, Slot>,
+    names: Vec<Reg<'a>>,
 }
 
-impl Memory {
-    fn new() -> Memory {
-        Memory {
-            table: MemTable::new(),
-        }
+impl<'a> Registers<'a> {
+    fn slot(&mut self, name: Reg<'a>) -> Slot {
+        *self.slots.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            self.names.len() - 1
+        })
     }
+}
+
+#[derive(Debug)]
+pub struct Program<'a> {
+    registers: Registers<'a>,
+    instructions: Vec<Instruction<'a>>,
+}
+
+/// Every register's value after each instruction, see `Program::history`.
+#[derive(Debug)]
+pub struct History<'a> {
+    registers: Vec<Reg<'a>>,
+    /// Source line of each instruction with the registers after it.
+    steps: Vec<(usize, Vec<Value>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Int(Value),
+    Cmp(Cmp),
+    Open,
+    Close,
+}
 
-    fn run_instruction(&mut self, cmd: &Instruction) -> Option<i32> {
-        let cmp_targ = *self.table.entry(cmd.cmp_targ).or_default();
-        let cond = cmd.cond;
-        let cmp_val = cmd.cmp_val;
+/// Splits a line into words, integers, comparisons and parentheses.
+fn tokenize(line: Span) -> ParseResult<Vec<(Token, Span)>> {
+    let s = line.as_str();
+    let run = |start: usize, f: fn(char) -> bool| {
+        s[start..]
+            .find(|c| !f(c))
+            .map_or(s.len(), |len| start + len)
+    };
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let is_digit = |c: char| c.is_ascii_digit();
+    let is_cmp = |c: char| "<>=!".contains(c);
 
-        let run_op = match cond {
-            Cond::Eq => cmp_targ == cmp_val,
-            Cond::Leq => cmp_targ <= cmp_val,
-            Cond::Lt => cmp_targ < cmp_val,
-            Cond::Gt => cmp_targ > cmp_val,
-            Cond::Geq => cmp_targ >= cmp_val,
-            Cond::Neq => cmp_targ != cmp_val,
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while let Some(c) = s[start..].chars().next() {
+        if c.is_whitespace() {
+            start += c.len_utf8();
+            continue;
+        }
+        let end = match c {
+            '(' | ')' => start + 1,
+            '-' => run(start + 1, is_digit),
+            c if is_cmp(c) => run(start, is_cmp),
+            c if is_word(c) => run(start, is_word),
+            c => start + c.len_utf8(),
+        };
+        let span = line.slice(start, end);
+        let token = match span.as_str() {
+            "(" => Token::Open,
+            ")" => Token::Close,
+            "-" => return Err(span.error("expected digits after `-`")),
+            text if c == '-' || text.chars().all(is_digit) => Token::Int(span.parse()?),
+            text if is_cmp(c) => Token::Cmp(
+                text.parse()
+                    .map_err(|_| span.error(format!("unknown comparison `{}`", text)))?,
+            ),
+            text if is_word(c) => Token::Word(text),
+            text => return Err(span.error(format!("unexpected `{}`", text))),
         };
+        tokens.push((token, span));
+        start = end;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens of one line. `if`, `and` and `or` are
+/// only keywords where an operand cannot stand, anywhere else they name
+/// registers like any other word:
+///
+/// ```text
+/// instruction = register operation operand ["if" or]
+/// or          = and {"or" and}
+/// and         = atom {"and" atom}
+/// atom        = "(" or ")" | operand comparison operand
+/// operand     = register | integer
+/// ```
+struct Parser<'a, 'r> {
+    line: Span<'a>,
+    number: usize,
+    tokens: Vec<(Token<'a>, Span<'a>)>,
+    pos: usize,
+    registers: &'r mut Registers<'a>,
+}
 
-        if run_op {
-            let op_targ = self.table.entry(cmd.target).or_default();
-            match cmd.op {
-                Operation::Inc => *op_targ += cmd.by,
-                Operation::Dec => *op_targ -= cmd.by,
+impl<'a> Parser<'a, '_> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(token, _)| token)
+    }
+
+    /// Error at the current token, or at the end of the line.
+    fn error(&self, expected: &str) -> parse::ParseError {
+        match self.tokens.get(self.pos) {
+            Some((_, span)) => {
+                span.error(format!("expected {}, found `{}`", expected, span.as_str()))
+            }
+            None => {
+                let end = self.line.as_str().len();
+                self.line
+                    .slice(end, end)
+                    .error(format!("expected {} at the end of the line", expected))
             }
-            Some(*op_targ)
-        } else {
-            None
         }
     }
-}
 
-fn parse_input(s: DayString) -> Program {
-    let mut v: Program = Vec::new();
+    fn eat(&mut self, token: Token) -> bool {
+        let found = self.peek() == Some(token);
+        self.pos += found as usize;
+        found
+    }
 
-    let re = Regex::new(r"(?m)^(?P<tar>\w+) (?P<op>inc|dec) (?P<by>(?:-)?\d+) if (?P<cpt>\w+) (?P<cmp>==|<|<=|>|>=|!=) (?P<cpv>(?:-)?\d+)$").unwrap();
-    for cap in re.captures_iter(s) {
-        let target = cap.name("tar").unwrap().as_str();
-        let op = cap.name("op").unwrap().as_str().parse().unwrap();
-        let by = cap.name("by").unwrap().as_str().parse().unwrap();
-        let cmp_targ = cap.name("cpt").unwrap().as_str();
-        let cond = cap.name("cmp").unwrap().as_str().parse().unwrap();
-        let cmp_val = cap.name("cpv").unwrap().as_str().parse().unwrap();
+    fn register(&mut self) -> ParseResult<Slot> {
+        match self.peek() {
+            Some(Token::Word(name)) => {
+                self.pos += 1;
+                Ok(self.registers.slot(name))
+            }
+            _ => Err(self.error("a register")),
+        }
+    }
+
+    fn operand(&mut self) -> ParseResult<Operand> {
+        match self.peek() {
+            Some(Token::Int(value)) => {
+                self.pos += 1;
+                Ok(Operand::Lit(value))
+            }
+            _ => self
+                .register()
+                .map(Operand::Reg)
+                .map_err(|_| self.error("a register or a number")),
+        }
+    }
 
-        v.push(Instruction {
+    fn instruction(&mut self) -> ParseResult<Instruction<'a>> {
+        let target = self.register()?;
+        let op = match self.peek() {
+            Some(Token::Word(word)) => word
+                .parse()
+                .map_err(|_| self.error("`inc`, `dec`, `set`, `mul`, `div` or `mod`"))?,
+            _ => return Err(self.error("an operation")),
+        };
+        self.pos += 1;
+        let by = self.operand()?;
+        let cond = match self.eat(Token::Word("if")) {
+            true => Some(self.or()?),
+            false => None,
+        };
+        if self.peek().is_some() {
+            return Err(self.error("`if`, `and` or `or`"));
+        }
+        Ok(Instruction {
+            line: self.line,
+            number: self.number,
             target,
             op,
             by,
-            cmp_targ,
             cond,
-            cmp_val,
-        });
+        })
+    }
+
+    fn or(&mut self) -> ParseResult<Cond> {
+        let mut cond = self.and()?;
+        while self.eat(Token::Word("or")) {
+            cond = Cond::Or(Box::new(cond), Box::new(self.and()?));
+        }
+        Ok(cond)
+    }
+
+    fn and(&mut self) -> ParseResult<Cond> {
+        let mut cond = self.atom()?;
+        while self.eat(Token::Word("and")) {
+            cond = Cond::And(Box::new(cond), Box::new(self.atom()?));
+        }
+        Ok(cond)
+    }
+
+    fn atom(&mut self) -> ParseResult<Cond> {
+        if self.eat(Token::Open) {
+            let cond = self.or()?;
+            if !self.eat(Token::Close) {
+                return Err(self.error("`)`"));
+            }
+            return Ok(cond);
+        }
+        let lhs = self.operand()?;
+        let Some(Token::Cmp(cmp)) = self.peek() else {
+            return Err(self.error("a comparison"));
+        };
+        self.pos += 1;
+        Ok(Cond::Compare(lhs, cmp, self.operand()?))
     }
+}
 
-    v
+/// One value per register slot, all starting at 0.
+struct Memory {
+    values: Vec<Value>,
 }
 
-fn solve_day(input: &Program) -> (i32, i32) {
-    let mut mem = Memory::new();
-    let part2 = input
-        .iter()
-        .filter_map(|instr| mem.run_instruction(instr))
-        .max()
-        .unwrap_or_default();
-    let part1 = mem.table.values().max().copied().unwrap_or_default();
-    (part1, part2)
+impl Memory {
+    fn value(&self, operand: Operand) -> Value {
+        match operand {
+            Operand::Reg(slot) => self.values[slot],
+            Operand::Lit(value) => value,
+        }
+    }
+
+    fn holds(&self, cond: &Cond) -> bool {
+        match cond {
+            Cond::Compare(a, cmp, b) => cmp.holds(self.value(*a), self.value(*b)),
+            Cond::And(a, b) => self.holds(a) && self.holds(b),
+            Cond::Or(a, b) => self.holds(a) || self.holds(b),
+        }
+    }
+
+    /// The target's new value if the condition held.
+    fn run_instruction(&mut self, cmd: &Instruction, names: &[Reg]) -> ParseResult<Option<Value>> {
+        if !cmd.cond.as_ref().is_none_or(|cond| self.holds(cond)) {
+            return Ok(None);
+        }
+        let by = self.value(cmd.by);
+        let old = self.values[cmd.target];
+        let text = cmd.line.as_str();
+        let new = match cmd.op {
+            Operation::Inc => old.checked_add(by),
+            Operation::Dec => old.checked_sub(by),
+            Operation::Set => Some(by),
+            Operation::Mul => old.checked_mul(by),
+            Operation::Div | Operation::Mod if by == 0 => {
+                return Err(cmd.line.error(format!("`{}` divides by zero", text)))
+            }
+            Operation::Div => old.checked_div(by),
+            Operation::Mod => old.checked_rem(by),
+        };
+        let new = new.ok_or_else(|| {
+            cmd.line.error(format!(
+                "`{}` overflows with `{}` at {} and an operand of {}",
+                text, names[cmd.target], old, by
+            ))
+        })?;
+        self.values[cmd.target] = new;
+        Ok(Some(new))
+    }
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let (sol1, sol2) = solve_day(&parsed);
-    [sol1.to_string(), sol2.to_string()]
+impl<'a> Program<'a> {
+    pub fn parse(s: &'a str) -> ParseResult<Self> {
+        parse_input(s)
+    }
+
+    /// Runs the program, showing `step` the registers after every
+    /// instruction. Returns the largest value at the end and the largest
+    /// value ever written.
+    fn run<F>(&self, mut step: F) -> ParseResult<(Value, Value)>
+    where
+        F: FnMut(&Instruction, &[Value]),
+    {
+        let mut mem = Memory {
+            values: vec![0; self.registers.names.len()],
+        };
+        let mut highest = 0;
+        for instr in &self.instructions {
+            if let Some(value) = mem.run_instruction(instr, &self.registers.names)? {
+                highest = highest.max(value);
+            }
+            step(instr, &mem.values);
+        }
+        let last = mem.values.iter().max().copied().unwrap_or_default();
+        Ok((last, highest))
+    }
+
+    pub fn history(&self) -> ParseResult<History<'a>> {
+        let mut steps = Vec::with_capacity(self.instructions.len());
+        self.run(|instr, values| steps.push((instr.number, values.to_vec())))?;
+        Ok(History {
+            registers: self.registers.names.clone(),
+            steps,
+        })
+    }
+}
+
+impl<'a> History<'a> {
+    /// The first time a register reached the highest value any register
+    /// held, as the step (counted from 1 as in `to_csv`), the register and
+    /// the value.
+    pub fn peak(&self) -> Option<(usize, Reg<'a>, Value)> {
+        let mut peak: Option<(usize, Reg, Value)> = None;
+        for (step, (_, values)) in self.steps.iter().enumerate() {
+            for (&name, &value) in self.registers.iter().zip(values) {
+                if peak.is_none_or(|(_, _, max)| value > max) {
+                    peak = Some((step + 1, name, value));
+                }
+            }
+        }
+        peak
+    }
+
+    /// One row per instruction with its line, then every register's value
+    /// and the highest value it has had so far.
+    pub fn to_csv(&self) -> String {
+        let mut out = "step,line".to_string();
+        for name in &self.registers {
+            let _ = write!(out, ",{},{}_max", name, name);
+        }
+        let mut maxima = vec![0; self.registers.len()];
+        for (step, (line, values)) in self.steps.iter().enumerate() {
+            let _ = write!(out, "\n{},{}", step + 1, line);
+            for (max, &value) in maxima.iter_mut().zip(values) {
+                *max = value.max(*max);
+                let _ = write!(out, ",{},{}", value, max);
+            }
+        }
+        out
+    }
+}
+
+fn parse_input(s: &str) -> ParseResult<Program<'_>> {
+    let mut registers = Registers::default();
+    let instructions = parse::lines(s)
+        .enumerate()
+        .filter(|(_, line)| !line.as_str().trim().is_empty())
+        .map(|(i, line)| {
+            let line = line.trim();
+            tokenize(line)
+                .and_then(|tokens| {
+                    Parser {
+                        line,
+                        number: i + 1,
+                        tokens,
+                        pos: 0,
+                        registers: &mut registers,
+                    }
+                    .instruction()
+                })
+                .map_err(|mut e| {
+                    e.msg = format!("{} in `{}`", e.msg, line.as_str());
+                    e
+                })
+        })
+        .collect::<ParseResult<_>>()?;
+    Ok(Program {
+        registers,
+        instructions,
+    })
+}
+
+fn solve_day(input: &Program) -> ParseResult<(Value, Value)> {
+    input.run(|_, _| ())
+}
+
+pub fn solve_str(s: &str) -> ParseResult<(Value, Value)> {
+    let parsed = parse_input(s)?;
+    let (sol1, sol2) = helpers::timed(|| solve_day(&parsed))?;
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(8))
+    let (sol1, sol2) = solve_str(helpers::read_day(8)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -153,7 +486,104 @@ mod tests {
 
     #[test]
     fn test_parts() {
-        let input = parse_input(helpers::read_example("08"));
-        assert_eq!(solve_day(&input), (1, 10));
+        let input = parse_input(helpers::read_example("08")).unwrap();
+        assert_eq!(solve_day(&input), Ok((1, 10)));
+    }
+
+    #[test]
+    fn test_language() {
+        let input = parse_input(
+            "a set 7\n\
+             b set a if a > 5 and (c == 0 or c < -3)\n\
+             a mul b if a==b\n\
+             a mod 10 if a >= 49 or b != b\n\
+             c dec a\n\
+             b div -3",
+        )
+        .unwrap();
+        assert_eq!(input.registers.names, ["a", "b", "c"]);
+        assert_eq!(
+            input.instructions[1].cond,
+            Some(Cond::And(
+                Box::new(Cond::Compare(Operand::Reg(0), Cmp::Gt, Operand::Lit(5))),
+                Box::new(Cond::Or(
+                    Box::new(Cond::Compare(Operand::Reg(2), Cmp::Eq, Operand::Lit(0))),
+                    Box::new(Cond::Compare(Operand::Reg(2), Cmp::Lt, Operand::Lit(-3))),
+                )),
+            ))
+        );
+        let history = input.history().unwrap();
+        assert_eq!(history.steps.last(), Some(&(6, vec![9, -2, -9])));
+        assert_eq!(history.peak(), Some((3, "a", 49)));
+
+        let error = |s| parse_input(s).unwrap_err();
+        let err = error("a inc 1 if b > 0\nb inc 1 if a =< 2");
+        assert_eq!((err.line, err.column), (2, 14));
+        assert_eq!(err.msg, "unknown comparison `=<` in `b inc 1 if a =< 2`");
+        let err = error("a inc 1 if (b > 0 or a < 1");
+        assert!(err
+            .msg
+            .starts_with("expected `)` at the end of the line in"));
+        assert_eq!(
+            error("a add 1").msg,
+            "expected `inc`, `dec`, `set`, `mul`, `div` or `mod`, found `add` in `a add 1`"
+        );
+        assert_eq!(
+            error("a inc 1 if").msg,
+            "expected a register or a number at the end of the line in `a inc 1 if`"
+        );
+        assert_eq!(
+            error("  a inc 1 b ").msg,
+            "expected `if`, `and` or `or`, found `b` in `a inc 1 b`"
+        );
+        assert_eq!(error("a inc 9999999999").column, 7);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = parse_input("a set 2147483000\nb set a\na inc 1000 if b > 0").unwrap();
+        let err = solve_day(&input).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(
+            err.msg,
+            "`a inc 1000 if b > 0` overflows with `a` at 2147483000 and an operand of 1000"
+        );
+
+        let input = parse_input("a set -2147483648\na div -1").unwrap();
+        assert_eq!(solve_day(&input).unwrap_err().line, 2);
+        let input = parse_input("a set 1\nb div a if a > 1\nb mod b").unwrap();
+        assert_eq!(
+            solve_day(&input).unwrap_err().msg,
+            "`b mod b` divides by zero"
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let input = parse_input(helpers::read_example("08")).unwrap();
+        let history = input.history().unwrap();
+        assert_eq!(history.peak(), Some((3, "c", 10)));
+        assert_eq!(
+            history.to_csv(),
+            "step,line,b,b_max,a,a_max,c,c_max\n\
+             1,1,0,0,0,0,0,0\n\
+             2,2,0,0,1,1,0,0\n\
+             3,3,0,0,1,1,10,10\n\
+             4,4,0,0,1,1,-10,10"
+        );
+    }
+
+    #[test]
+    fn test_keyword_registers() {
+        let input = parse_input(
+            "if inc 2 if and < 1\n\
+             and inc 1 if if > 1 and or == 0 or and < 0\n\
+             or set if if and > 0 and if >= 2\n\
+             1a dec if",
+        )
+        .unwrap();
+        assert_eq!(input.registers.names, ["if", "and", "or", "1a"]);
+        let history = input.history().unwrap();
+        assert_eq!(history.steps.last(), Some(&(4, vec![2, 1, 2, -2])));
     }
 }
@@ -1,82 +1,259 @@
-use crate::helpers::{self, DayString, Solution};
+use crate::helpers::parse::{ParseResult, Span};
+use crate::helpers::{self, Solution};
 
-use nom::{IResult, Parser};
+use std::fmt;
+use std::str::CharIndices;
 
-use nom::character::complete::{char, none_of};
-use nom::multi::{fold_many0, separated_list0};
-use nom::sequence::delimited;
-
-#[derive(Debug)]
-enum Stream {
+#[derive(Debug, PartialEq, Eq)]
+pub enum Stream {
     Group(Vec<Stream>),
-    Garbage(u16),
+    Garbage(usize),
 }
 
-fn stream_parser(rem: &str) -> IResult<&str, Stream> {
-    group_parser.or(garbage_parser).parse(rem)
+impl Drop for Stream {
+    /// Takes deep trees apart without recursing.
+    fn drop(&mut self) {
+        let Stream::Group(items) = self else {
+            return;
+        };
+        let mut stack = std::mem::take(items);
+        while let Some(mut stream) = stack.pop() {
+            if let Stream::Group(items) = &mut stream {
+                stack.append(items);
+            }
+        }
+    }
 }
 
-fn in_garbage(rem: &str) -> IResult<&str, u16> {
-    let (input, c) = none_of(">")(rem)?;
-    if c == '!' {
-        return Ok((&input[1..], 0));
-    }
-    Ok((input, 1))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Unexpected(char),
+    UnclosedGroup { start: usize },
+    UnclosedGarbage { start: usize },
+    TrailingData,
+    Empty,
 }
 
-fn garbage_parser(rem: &str) -> IResult<&str, Stream> {
-    let (input, l) = delimited(
-        char('<'),
-        fold_many0(in_garbage, u16::default, |a, b| a + b),
-        char('>'),
-    )(rem)?;
-    Ok((input, Stream::Garbage(l)))
+/// A problem in a stream, at a byte offset and inside the groups that are
+/// open there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamError {
+    pub offset: usize,
+    /// Byte offsets of the `{` of the enclosing groups, outermost first.
+    pub open: Vec<usize>,
+    pub kind: ErrorKind,
 }
 
-fn group_parser(rem: &str) -> IResult<&str, Stream> {
-    let (input, v) = delimited(
-        char('{'),
-        separated_list0(char(','), stream_parser),
-        char('}'),
-    )(rem)?;
-    Ok((input, Stream::Group(v)))
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Unexpected(c) => write!(f, "unexpected `{}`", c)?,
+            ErrorKind::UnclosedGroup { start } => {
+                write!(f, "group opened at byte {} is never closed", start)?
+            }
+            ErrorKind::UnclosedGarbage { start } => {
+                write!(f, "garbage opened at byte {} is never closed", start)?
+            }
+            ErrorKind::TrailingData => write!(f, "unexpected data after the outermost group")?,
+            ErrorKind::Empty => write!(f, "the stream is empty")?,
+        }
+        write!(f, " at byte {}", self.offset)?;
+        if !self.open.is_empty() {
+            let open: Vec<String> = self.open.iter().map(usize::to_string).collect();
+            write!(
+                f,
+                " (depth {}, in the groups opened at bytes {})",
+                self.open.len(),
+                open.join(", ")
+            )?;
+        }
+        Ok(())
+    }
 }
 
-fn parse_input(s: DayString) -> Stream {
-    stream_parser(s).unwrap().1
+impl std::error::Error for StreamError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// A group or garbage, after a `,` or at the start.
+    Item,
+    /// Right after a `{`.
+    ItemOrClose,
+    CommaOrClose,
 }
 
-fn solve_part1(input: &Stream) -> u16 {
-    fn total_score(stream: &Stream, depth: u16) -> u16 {
-        match stream {
-            Stream::Garbage(_) => 0,
-            Stream::Group(v) => depth + v.iter().map(|x| total_score(x, depth + 1)).sum::<u16>(),
+/// Reads a stream without recursing, so deep nesting is fine. Stops at the
+/// first problem unless `recover` is set, then it notes the problem, skips
+/// what it cannot use and closes whatever is left open at the end.
+struct StreamParser<'a> {
+    s: &'a str,
+    recover: bool,
+    errors: Vec<StreamError>,
+}
+
+impl StreamParser<'_> {
+    fn fail(
+        &mut self,
+        offset: usize,
+        open: &[(usize, Vec<Stream>)],
+        kind: ErrorKind,
+    ) -> Result<(), StreamError> {
+        let error = StreamError {
+            offset,
+            open: open.iter().map(|&(start, _)| start).collect(),
+            kind,
+        };
+        match self.recover {
+            true => self.errors.push(error),
+            false => return Err(error),
         }
+        Ok(())
     }
 
-    total_score(input, 1)
+    /// Characters of garbage opened at `start` that are not cancelled.
+    fn garbage(
+        &mut self,
+        start: usize,
+        chars: &mut CharIndices,
+        open: &[(usize, Vec<Stream>)],
+    ) -> Result<usize, StreamError> {
+        let mut count = 0;
+        while let Some((_, c)) = chars.next() {
+            match c {
+                '>' => return Ok(count),
+                '!' => {
+                    chars.next();
+                }
+                _ => count += 1,
+            }
+        }
+        self.fail(self.s.len(), open, ErrorKind::UnclosedGarbage { start })?;
+        Ok(count)
+    }
+
+    fn run(&mut self) -> Result<Stream, StreamError> {
+        // Every open group with the offset of its `{` and what it holds.
+        let mut open: Vec<(usize, Vec<Stream>)> = Vec::new();
+        let mut root = None;
+        let mut expect = Expect::Item;
+        let mut chars = self.s.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            if root.is_some() {
+                if !c.is_whitespace() {
+                    self.fail(i, &open, ErrorKind::TrailingData)?;
+                    break;
+                }
+                continue;
+            }
+            let item = match (c, expect) {
+                ('{', Expect::Item | Expect::ItemOrClose) => {
+                    open.push((i, Vec::new()));
+                    expect = Expect::ItemOrClose;
+                    continue;
+                }
+                ('<', Expect::Item | Expect::ItemOrClose) => {
+                    Stream::Garbage(self.garbage(i, &mut chars, &open)?)
+                }
+                (',', Expect::CommaOrClose) => {
+                    expect = Expect::Item;
+                    continue;
+                }
+                ('}', _) if !open.is_empty() => {
+                    if expect == Expect::Item {
+                        self.fail(i, &open, ErrorKind::Unexpected(c))?;
+                    }
+                    Stream::Group(open.pop().unwrap().1)
+                }
+                _ => {
+                    self.fail(i, &open, ErrorKind::Unexpected(c))?;
+                    continue;
+                }
+            };
+            match open.last_mut() {
+                Some((_, items)) => items.push(item),
+                None => root = Some(item),
+            }
+            expect = Expect::CommaOrClose;
+        }
+
+        while let Some((start, items)) = open.pop() {
+            self.fail(self.s.len(), &open, ErrorKind::UnclosedGroup { start })?;
+            match open.last_mut() {
+                Some((_, outer)) => outer.push(Stream::Group(items)),
+                None => root = Some(Stream::Group(items)),
+            }
+        }
+        match root {
+            Some(stream) => Ok(stream),
+            None => {
+                self.fail(self.s.len(), &open, ErrorKind::Empty)?;
+                Ok(Stream::Group(Vec::new()))
+            }
+        }
+    }
 }
 
-fn solve_part2(input: &Stream) -> u16 {
-    fn total_garbage(stream: &Stream) -> u16 {
-        match stream {
-            Stream::Garbage(i) => *i,
-            Stream::Group(v) => v.iter().map(total_garbage).sum::<u16>(),
+/// The stream in `s`, or its first problem.
+pub fn parse_stream(s: &str) -> Result<Stream, StreamError> {
+    StreamParser {
+        s,
+        recover: false,
+        errors: Vec::new(),
+    }
+    .run()
+}
+
+/// The stream in `s` with unexpected characters skipped and unterminated
+/// groups and garbage closed at the end, and every problem found.
+pub fn recover_stream(s: &str) -> (Stream, Vec<StreamError>) {
+    let mut parser = StreamParser {
+        s,
+        recover: true,
+        errors: Vec::new(),
+    };
+    let stream = parser.run().expect("Recovering parser should not fail");
+    (stream, parser.errors)
+}
+
+fn parse_input(s: &str) -> ParseResult<Stream> {
+    parse_stream(s).map_err(|e| Span::new(s).slice(e.offset, e.offset).error(e.to_string()))
+}
+
+fn solve_part1(input: &Stream) -> usize {
+    let mut score = 0;
+    let mut stack = vec![(input, 1)];
+    while let Some((stream, depth)) = stack.pop() {
+        if let Stream::Group(items) = stream {
+            score += depth;
+            stack.extend(items.iter().map(|item| (item, depth + 1)));
         }
     }
+    score
+}
 
-    total_garbage(input)
+fn solve_part2(input: &Stream) -> usize {
+    let mut garbage = 0;
+    let mut stack = vec![input];
+    while let Some(stream) = stack.pop() {
+        match stream {
+            Stream::Garbage(n) => garbage += n,
+            Stream::Group(items) => stack.extend(items),
+        }
+    }
+    garbage
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(9))
+    let (sol1, sol2) = solve_str(helpers::read_day(9)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -85,13 +262,89 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input1 = parse_input("{{<a>},{<a>},{<a>},{<a>}}");
+        let input1 = parse_input("{{<a>},{<a>},{<a>},{<a>}}").unwrap();
         assert_eq!(solve_part1(&input1), 9);
+        let input2 = parse_input("{{<!!>},{<!!>},{<!!>},{<!!>}}").unwrap();
+        assert_eq!(solve_part1(&input2), 9);
+        let input3 = parse_input("{{<a!>},{<a!>},{<a!>},{<ab>}}").unwrap();
+        assert_eq!(solve_part1(&input3), 3);
     }
 
     #[test]
     fn test_part2() {
-        let input1 = parse_input("{{<a>},{<a>},{<a>},{<a>}}");
+        let input1 = parse_input("{{<a>},{<a>},{<a>},{<a>}}").unwrap();
         assert_eq!(solve_part2(&input1), 4);
+        let input2 = parse_input("<{o\"i!a,<{i<a>").unwrap();
+        assert_eq!(solve_part2(&input2), 10);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse_stream("{{<a>},{x}}").unwrap_err();
+        assert_eq!(
+            err,
+            StreamError {
+                offset: 8,
+                open: vec![0, 7],
+                kind: ErrorKind::Unexpected('x'),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unexpected `x` at byte 8 (depth 2, in the groups opened at bytes 0, 7)"
+        );
+        assert_eq!(
+            parse_stream("{{},}").unwrap_err().kind,
+            ErrorKind::Unexpected('}')
+        );
+        assert_eq!(parse_stream("{}\n").unwrap(), Stream::Group(Vec::new()));
+        assert_eq!(
+            parse_stream("{} {}").unwrap_err().kind,
+            ErrorKind::TrailingData
+        );
+        assert_eq!(parse_stream("").unwrap_err().kind, ErrorKind::Empty);
+
+        let err = parse_input("{{<a>},{<\n!>}}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert!(err
+            .msg
+            .starts_with("garbage opened at byte 8 is never closed"));
+    }
+
+    #[test]
+    fn test_recover() {
+        let (stream, errors) = recover_stream("{{<a>}x,{{<b");
+        let kinds: Vec<ErrorKind> = errors.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                ErrorKind::Unexpected('x'),
+                ErrorKind::UnclosedGarbage { start: 10 },
+                ErrorKind::UnclosedGroup { start: 9 },
+                ErrorKind::UnclosedGroup { start: 8 },
+                ErrorKind::UnclosedGroup { start: 0 },
+            ]
+        );
+        assert_eq!(errors[2].open, [0, 8]);
+        assert_eq!(
+            (solve_part1(&stream), solve_part2(&stream)),
+            (1 + 2 + 2 + 3, 2)
+        );
+
+        let (stream, errors) = recover_stream("{<>}}");
+        assert_eq!(stream, Stream::Group(vec![Stream::Garbage(0)]));
+        assert_eq!(errors[0].kind, ErrorKind::TrailingData);
+    }
+
+    #[test]
+    fn test_large() {
+        let n = 200_000;
+        let deep = "{".repeat(n) + &"}".repeat(n);
+        let input = parse_input(&deep).unwrap();
+        assert_eq!(solve_part1(&input), n * (n + 1) / 2);
+        drop(input);
+
+        let long = format!("{{<{}>}}", "a".repeat(70_000));
+        assert_eq!(solve_str(&long), Ok((1, 70_000)));
     }
 }
@@ -1,5 +1,6 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 use std::ops::{Index, IndexMut};
 
 #[derive(Debug)]
@@ -43,8 +44,8 @@ impl IndexMut<usize> for StringCircle {
     }
 }
 
-fn parse_numbers(s: DayString) -> Vec<u8> {
-    s.split(',').filter_map(|x| x.parse().ok()).collect()
+fn parse_numbers(s: &str) -> ParseResult<Vec<u8>> {
+    parse::list(Span::new(s), ",")
 }
 
 fn solve_part1(lengths: &[u8]) -> usize {
@@ -107,15 +108,16 @@ pub fn knot_hash_str(string: &str) -> String {
     as_hex(&knot_hash(string.as_bytes()))
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_numbers(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = knot_hash_str(s);
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, String)> {
+    let parsed = parse_numbers(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| knot_hash_str(s));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(10))
+    let (sol1, sol2) = solve_str(helpers::read_day(10)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -125,6 +127,6 @@ mod tests {
     #[test]
     fn test_part2() {
         let input = "";
-        assert_eq!(knot_hash_str(&input), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(knot_hash_str(input), "a2582a3a0e66e6e86e3812dcb672a272");
     }
 }
@@ -1,24 +1,27 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
-use regex::Regex;
+use crate::helpers::parse::{self, ParseResult};
+use crate::helpers::Solution;
 
 type Node = usize;
 type Graph = Vec<Vec<Node>>;
 
-fn parse_input(s: DayString) -> Graph {
-    let re = Regex::new(r"(?m)^(\d+) <-> ((?:\d+(?:, )?)*)$").unwrap();
+fn parse_input(s: &str) -> ParseResult<Graph> {
     let mut all = Vec::new();
-    for cap in re.captures_iter(s) {
-        let neighbs = cap
-            .get(2)
-            .unwrap()
-            .as_str()
-            .split(", ")
-            .map(|x| x.parse().unwrap())
-            .collect();
+    let mut lines = Vec::new();
+    for line in parse::lines(s) {
+        let (node, neighbs): (Node, Vec<Node>) = parse::adjacency(line, "<->")?;
+        if node != all.len() {
+            return Err(line.error(format!("expected node {}, found {}", all.len(), node)));
+        }
         all.push(neighbs);
+        lines.push(line);
+    }
+    for (line, neighbs) in lines.iter().zip(&all) {
+        if let Some(nb) = neighbs.iter().find(|&&nb| nb >= all.len()) {
+            return Err(line.error(format!("unknown node {}", nb)));
+        }
     }
-    all
+    Ok(all)
 }
 
 fn dfs(g: &Graph, s: Node, found: &mut [bool]) {
@@ -49,13 +52,14 @@ fn solve_part2(graph: &Graph) -> usize {
     counter
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(12))
+    let (sol1, sol2) = solve_str(helpers::read_day(12)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
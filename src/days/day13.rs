@@ -1,22 +1,15 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
-
-use nom::bytes::complete as ncmp;
-use nom::character::complete as ccmp;
-use nom::sequence::terminated;
-use nom::Parser;
+use crate::helpers::parse::{self, ParseResult};
+use crate::helpers::Solution;
 
 type Wall = [u32; 2];
 type Firewall = Vec<Wall>;
 
-fn parse_input(s: DayString) -> Firewall {
-    let mut parser = terminated(ccmp::u32::<&str, ()>, ncmp::tag(": ")).and(ccmp::u32);
-    s.lines()
-        .map(|ln| {
-            let (_, (a, b)) = parser.parse(ln).unwrap();
-            [a, b]
-        })
-        .collect()
+fn parse_input(s: &str) -> ParseResult<Firewall> {
+    parse::parse_lines(s, |ln| {
+        let (depth, range) = parse::key_value(ln, ":")?;
+        Ok([depth, range])
+    })
 }
 
 fn solve_part1(input: &[Wall]) -> u32 {
@@ -35,19 +28,20 @@ fn solve_part2(walls: &[Wall]) -> u32 {
     let is_sneaky = |wait: &u32| -> bool {
         walls
             .iter()
-            .all(|&[depth, range]| range != 1 && ((depth + wait) % (2 * (range - 1)) != 0))
+            .all(|&[depth, range]| range != 1 && !(depth + wait).is_multiple_of(2 * (range - 1)))
     };
 
     (0..u32::MAX).find(is_sneaky).unwrap()
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(u32, u32)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(13))
+    let (sol1, sol2) = solve_str(helpers::read_day(13)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
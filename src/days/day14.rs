@@ -1,6 +1,7 @@
 use crate::day10::knot_hash;
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::ParseResult;
+use crate::helpers::Solution;
 
 type Squares = [[u8; 16]; 128];
 type Pos = (i16, i16);
@@ -27,12 +28,14 @@ impl Disk {
     fn regions(&self) -> usize {
         let mut visit = [[false; 128]; 128];
         let mut regions = 0;
+        let mut visits = 0;
 
         for x in 0..128 {
             for y in 0..128 {
                 if !visit[x as usize][y as usize] && self.is_square((x, y)) {
                     regions += 1;
-                    self.dfs((x, y), &mut visit);
+                    crate::trace!(Debug, regions, "region starts at {:?}", (x, y));
+                    self.dfs((x, y), &mut visit, &mut visits);
                 }
             }
         }
@@ -40,21 +43,22 @@ impl Disk {
         regions
     }
 
-    fn dfs(&self, pos: Pos, visit: &mut Visit) {
+    fn dfs(&self, pos: Pos, visit: &mut Visit, visits: &mut usize) {
         let (x, y) = pos;
         if !self.is_square(pos) || visit[x as usize][y as usize] {
             return;
         }
-        //dbg!(pos);
+        *visits += 1;
+        crate::trace!(Trace, *visits, "visit {:?}", pos);
 
         visit[x as usize][y as usize] = true;
         for nb_pos in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
-            self.dfs(nb_pos, visit)
+            self.dfs(nb_pos, visit, visits)
         }
     }
 }
 
-fn get_squares(original: DayString) -> Squares {
+fn get_squares(original: &str) -> Squares {
     let mut result = [[0; 16]; 128];
     for (i, val) in result.iter_mut().enumerate() {
         let new_str = original.to_string() + "-" + &i.to_string();
@@ -79,15 +83,16 @@ fn solve_part2(area: Squares) -> usize {
     disk.regions()
 }
 
-fn solve_string(s: DayString) -> Solution {
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
     let squares = get_squares(s);
-    let sol1 = solve_part1(&squares).to_string();
-    let sol2 = solve_part2(squares).to_string();
-    [sol1, sol2]
+    let sol1 = helpers::timed(|| solve_part1(&squares));
+    let sol2 = helpers::timed(|| solve_part2(squares));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(14))
+    let (sol1, sol2) = solve_str(helpers::read_day(14)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
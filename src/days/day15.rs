@@ -1,5 +1,6 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 
 const A_FACTOR: usize = 16807;
 const B_FACTOR: usize = 48271;
@@ -11,21 +12,18 @@ struct Generator {
     value: usize,
 }
 
-fn parse_input(s: DayString) -> [Generator; 2] {
-    fn get_number(s: &str) -> usize {
-        s.chars()
-            .filter(|c| c.is_numeric())
-            .collect::<String>()
-            .parse()
-            .unwrap()
+fn parse_input(s: &str) -> ParseResult<[Generator; 2]> {
+    fn get_number(line: Span) -> ParseResult<usize> {
+        line.split_once(" starts with ")?.1.parse()
     }
-    let mut lines = s.lines();
-    let gen_a_value = lines.next().map(get_number).unwrap_or_default();
-    let gen_b_value = lines.next().map(get_number).unwrap_or_default();
-    [
+    let values = parse::parse_lines(s, get_number)?;
+    let [gen_a_value, gen_b_value] = values[..] else {
+        return Err(Span::new(s).error(format!("expected 2 generators, found {}", values.len())));
+    };
+    Ok([
         Generator::new(A_FACTOR, gen_a_value),
         Generator::new(B_FACTOR, gen_b_value),
-    ]
+    ])
 }
 
 impl Generator {
@@ -72,15 +70,16 @@ fn solve_part2([a, b]: [Generator; 2]) -> usize {
     run_judge(a, b, 5000000)
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(parsed).to_string();
-    let sol2 = solve_part2(parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(parsed));
+    let sol2 = helpers::timed(|| solve_part2(parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(15))
+    let (sol1, sol2) = solve_str(helpers::read_day(15)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -89,13 +88,13 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("15"));
+        let input = parse_input(helpers::read_example("15")).unwrap();
         assert_eq!(solve_part1(input), 588)
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("15"));
+        let input = parse_input(helpers::read_example("15")).unwrap();
         assert_eq!(solve_part2(input), 309)
     }
 }
@@ -1,10 +1,14 @@
+use std::fmt;
 use std::str::FromStr;
 
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{ParseResult, Span};
+use crate::helpers::Solution;
 
 type Dance = Vec<Move>;
 
+const DANCERS: &str = "abcdefghijklmnop";
+
 #[derive(Debug, Clone, Copy)]
 enum Move {
     Spin(u8),
@@ -12,14 +16,38 @@ enum Move {
     Partner(char, char)
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MoveError {
+    Unknown(String),
+    NoSlash(String),
+    Spin(String),
+    Position(String),
+    Dancer(String),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::Unknown(s) => write!(f, "unknown move `{}`", s),
+            MoveError::NoSlash(s) => write!(f, "expected `/` in `{}`", s),
+            MoveError::Spin(s) => write!(f, "`{}` is not a spin from 0 to {}", s, DANCERS.len()),
+            MoveError::Position(s) => {
+                write!(f, "`{}` is not a position from 0 to {}", s, DANCERS.len() - 1)
+            }
+            MoveError::Dancer(s) => write!(f, "`{}` is not a dancer from `a` to `p`", s),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Dancers {
-    dancers: Vec<char>
+    dancers: Vec<char>,
+    moves: usize,
 }
 
 // abcdefg - gabcdef
 impl Dancers {
-    fn new() -> Self { Dancers { dancers: "abcdefghijklmnop".chars().collect() } }
+    fn new() -> Self { Dancers { dancers: DANCERS.chars().collect(), moves: 0 } }
     
     fn dance_move(&mut self, mov: &Move) {
         match mov {
@@ -37,47 +65,66 @@ impl Dancers {
                 (self.dancers[x], self.dancers[y]) = (self.dancers[y], self.dancers[x]);
             },
             Move::Partner(a, b) => {
-                let apos = self.dancers.iter().position(|x| x == a).unwrap_or_default();
-                let bpos = self.dancers.iter().position(|x| x == b).unwrap_or_default();
+                let position = |d| {
+                    self.dancers.iter().position(|x| x == d).expect("Dancer should be on the floor")
+                };
+                let (apos, bpos) = (position(a), position(b));
                 (self.dancers[apos], self.dancers[bpos]) = (self.dancers[bpos], self.dancers[apos]);
             },
         }
+        self.moves += 1;
+        crate::trace!(Trace, self.moves, "{:?} -> {}", mov, self);
     }
 }
 
-impl ToString for Dancers {
-    fn to_string(&self) -> String {
-        self.dancers.iter().collect()
+impl fmt::Display for Dancers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.dancers.iter().try_for_each(|c| write!(f, "{}", c))
     }
 }
 
 impl FromStr for Move {
-    type Err = ();
+    type Err = MoveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let rest = &s[1..];
+        let rest = s.get(1..).unwrap_or_default();
+        let pair = || rest.split_once('/').ok_or_else(|| MoveError::NoSlash(s.to_string()));
+        let position = |x: &str| {
+            x.parse()
+                .ok()
+                .filter(|&x: &u8| (x as usize) < DANCERS.len())
+                .ok_or_else(|| MoveError::Position(x.to_string()))
+        };
+        let dancer = |x: &str| match x.chars().collect::<Vec<char>>()[..] {
+            [c] if DANCERS.contains(c) => Ok(c),
+            _ => Err(MoveError::Dancer(x.to_string())),
+        };
 
         match s.chars().next() {
-            Some('s') => rest.parse().map(Move::Spin).or(Err(())),
-            Some('x') => { 
-                let (x,y) = rest.split_once('/').ok_or(())?;
-                let x = x.parse().map_err(|_| ())?;
-                let y = y.parse().map_err(|_| ())?;
-                Ok(Move::Exchange(x, y))
+            Some('s') => rest
+                .parse()
+                .ok()
+                .filter(|&x: &u8| x as usize <= DANCERS.len())
+                .map(Move::Spin)
+                .ok_or_else(|| MoveError::Spin(rest.to_string())),
+            Some('x') => {
+                let (x, y) = pair()?;
+                Ok(Move::Exchange(position(x)?, position(y)?))
             },
             Some('p') => {
-                let (x,y) = rest.split_once('/').ok_or(())?;
-                let c1 = x.chars().next().ok_or(())?;
-                let c2 = y.chars().next().ok_or(())?;
-                Ok(Move::Partner(c1, c2))
+                let (x, y) = pair()?;
+                Ok(Move::Partner(dancer(x)?, dancer(y)?))
             },
-            _ => Err(()),
+            _ => Err(MoveError::Unknown(s.to_string())),
         }
     }
 }
 
-fn parse_input(s: DayString) -> Dance {
-    s.split(',').flat_map(|mv| mv.parse()).collect()
+fn parse_input(s: &str) -> ParseResult<Dance> {
+    Span::new(s)
+        .split(",")
+        .map(|tok| tok.as_str().parse().map_err(|e: MoveError| tok.error(e.to_string())))
+        .collect()
 }
 
 fn solve_part1(moves: &[Move]) -> Dancers {
@@ -100,17 +147,18 @@ fn solve_part2(dancers: &mut Dancers, moves: &[Move]) {
     moves.iter().cycle().take(amount).for_each(|x| dancers.dance_move(x));
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let mut dancers = solve_part1(&parsed);
+pub fn solve_str(s: &str) -> ParseResult<(String, String)> {
+    let parsed = parse_input(s)?;
+    let mut dancers = helpers::timed(|| solve_part1(&parsed));
     let sol1 = dancers.to_string();
-    solve_part2(&mut dancers, &parsed);
+    helpers::timed(|| solve_part2(&mut dancers, &parsed));
     let sol2 = dancers.to_string();
-    [sol1, sol2]
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(16))
+    let (sol1, sol2) = solve_str(helpers::read_day(16)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -120,7 +168,16 @@ mod tests {
     #[test]
     fn test_parse() {
         let input = helpers::read_day(16);
-        parse_input(input);
+        parse_input(input).unwrap();
+        assert!(parse_input("s1,q3/4").is_err());
+
+        let error = |s| parse_input(s).unwrap_err();
+        assert_eq!(error("s1,s17").msg, "`17` is not a spin from 0 to 16");
+        assert_eq!(error("s1,x3/99").column, 4);
+        assert_eq!(error("x3/99").msg, "`99` is not a position from 0 to 15");
+        assert_eq!(error("pa/z").msg, "`z` is not a dancer from `a` to `p`");
+        assert_eq!(error("pab/c").msg, "`ab` is not a dancer from `a` to `p`");
+        assert_eq!(error("x3-4").msg, "expected `/` in `x3-4`");
     }
 
     #[test]
@@ -129,12 +186,12 @@ mod tests {
         dancers.dance_move(&Move::Spin(1));
         assert_eq!(dancers.to_string(), "pabcdefghijklmno");
 
-        let mut dancers = Dancers { dancers: vec!['a', 'b', 'c', 'd', 'e'] };
-        let moves = parse_input("s1,x3/4,pe/b");
+        let mut dancers = Dancers { dancers: vec!['a', 'b', 'c', 'd', 'e'], moves: 0 };
+        let moves = parse_input("s1,x3/4,pe/b").unwrap();
         moves.iter().for_each(|mov| dancers.dance_move(mov));
         assert_eq!(dancers.to_string(), "baedc");
 
-        let mut dancers = Dancers { dancers: vec!['a', 'b', 'c', 'd', 'e'] };
+        let mut dancers = Dancers { dancers: vec!['a', 'b', 'c', 'd', 'e'], moves: 0 };
         dancers.dance_move(&Move::Spin(1));
         assert_eq!(dancers.to_string(), "eabcd");
 
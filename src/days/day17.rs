@@ -1,5 +1,6 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{ParseResult, Span};
+use crate::helpers::Solution;
 
 struct Circular {
     elements: Vec<CircNode>,
@@ -53,8 +54,8 @@ impl Circular {
     }
 }
 
-fn parse_input(s: DayString) -> usize {
-    s.parse().unwrap()
+fn parse_input(s: &str) -> ParseResult<usize> {
+    Span::new(s).trim().parse()
 }
 
 fn solve_part1(n: usize) -> u32 {
@@ -87,15 +88,16 @@ fn solve_part2(n: usize) -> usize {
     result
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(parsed).to_string();
-    let sol2 = solve_part2(parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(u32, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(parsed));
+    let sol2 = helpers::timed(|| solve_part2(parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(17))
+    let (sol1, sol2) = solve_str(helpers::read_day(17)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
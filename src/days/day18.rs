@@ -6,7 +6,8 @@ use std::thread;
 use std::time::Duration;
 
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult};
+use crate::helpers::Solution;
 
 const WAIT:Duration = Duration::from_millis(1);
 const P:Reg = Reg { address:15 };
@@ -50,7 +51,8 @@ struct Cpu {
     memory: Memory,
     last_sound: i128,
     channel: Option<(mpsc::Sender<i128>, mpsc::Receiver<i128>)>,
-    _name: String,
+    name: String,
+    steps: usize,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -94,7 +96,8 @@ impl Cpu {
             memory,
             last_sound: 0,
             channel: None,
-            _name: name,
+            name,
+            steps: 0,
         }
     }
 
@@ -114,11 +117,13 @@ impl Cpu {
         let program = &self.program;
         let pointer = self.pointer;
         self.pointer += 1;
+        self.steps += 1;
+        crate::trace!(Trace, self.steps, "{} @{}: {:?}", self.name, pointer, program.get(pointer));
 
         match program.get(pointer).copied() {
             Some(Command::Snd(val)) => {
                 let loc = self.get_value(val);
-                //println!("{}, {}", loc, &self.name);
+                crate::trace!(Debug, self.steps, "{} sends {}", self.name, loc);
                 if let Some((tx, _)) = &self.channel {
                     tx.send(loc).unwrap();
                     return Status::Sending;
@@ -252,12 +257,12 @@ impl FromStr for Command {
     }
 }
 
-fn parse_input(s: DayString) -> Program {
-    s.lines().flat_map(|s| s.parse()).collect()
+fn parse_input(s: &str) -> ParseResult<Program> {
+    parse::parse_lines(s, |ln| ln.trim().parse())
 }
 
 fn solve_part1(input: &Program) -> i128 {
-    let mut cpu = Cpu::new(input.clone(), "".to_string());
+    let mut cpu = Cpu::new(input.clone(), "cpu".to_string());
     let result = cpu.find(|x| matches!(x, Status::Rcv(_)));
     if let Status::Rcv(x) = result.expect("should receive once!") {
         return x;
@@ -299,19 +304,19 @@ fn solve_part2(input: &Program) -> usize {
     });
 
     let _count0 = thread0.join().expect("Calculation should finish without issue!");
-    let count1 = thread1.join().expect("Calculation in Thread 1 encountered trouble!");
-    count1
+    thread1.join().expect("Calculation in Thread 1 encountered trouble!")
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(i128, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(18))
+    let (sol1, sol2) = solve_str(helpers::read_day(18)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -320,14 +325,14 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("18"));
+        let input = parse_input(helpers::read_example("18")).unwrap();
         assert_eq!(solve_part1(&input), 4);
     }
 
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("18_2"));
+        let input = parse_input(helpers::read_example("18_2")).unwrap();
         assert_eq!(solve_part2(&input), 3);
     }
 }
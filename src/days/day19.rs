@@ -1,21 +1,17 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::geom::{Dir, Point2};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 use ndarray;
 
 type Path = ndarray::Array2<Field>;
-type Pos = [usize; 2];
-type Dir = [isize; 2];
+type Pos = Point2<isize>;
 
-fn rot_left([x,y]:Dir) -> Dir {
-    [-y, x]
-}
-
-fn rot_right([x,y]:Dir) -> Dir {
-    [y, -x]
-}
-
-fn go([x,y]:Pos, [xx, yy]:Dir) -> Pos {
-    [(x as isize + xx) as usize, (y as isize + yy) as usize]
+fn field_at(path: &Path, pos: Pos) -> Field {
+    pos.into_index()
+        .and_then(|idx| path.get(idx))
+        .copied()
+        .unwrap_or(Field::Empty)
 }
 
 #[derive(Debug)]
@@ -38,19 +34,18 @@ struct ParseFieldError;
 
 impl Walker {
     fn step(&mut self) {
-        self.pos = go(self.pos, self.dir);
+        self.pos += self.dir;
     }
 
     fn walk(&mut self, path:&Path, res: &mut Vec<char>) -> bool {
-        let here = path[self.pos];
+        let here = field_at(path, self.pos);
         match here {
             Field::Vert => { self.step(); true },
             Field::Hor => {self.step(); true },
             Field::Cross => {
-                let left_dir = rot_left(self.dir);
-                let right_dir = rot_right(self.dir);
-                let left_field = go(self.pos, left_dir);
-                match path[left_field] {
+                let left_dir = self.dir.turn_left();
+                let right_dir = self.dir.turn_right();
+                match field_at(path, self.pos + left_dir) {
                     Field::Empty => {
                         self.dir = right_dir;
                         self.step()
@@ -73,7 +68,7 @@ impl Walker {
 
     fn start(path:&Path) -> Walker {
         let (i, _) = path.row(0).iter().enumerate().find(|(_, f)| matches!(f, Field::Vert)).expect("No start found!");
-        Walker { pos: [0, i], dir: [1, 0] }
+        Walker { pos: Point2::new(i as isize, 0), dir: Dir::Down }
     }
 }
 
@@ -92,32 +87,37 @@ impl TryInto<Field> for char {
     }
 }
 
-fn right_pad(s: &str, n: usize, symb: char) -> String {
-    let new = s.to_string();
-    let to_add = n.saturating_sub(new.len());
-    new + &symb.to_string().repeat(to_add)
-}
+fn parse_input(s: &str) -> ParseResult<Path> {
+    let rows = parse::parse_lines(s, |line| {
+        line.as_str()
+            .char_indices()
+            .map(|(i, c)| {
+                c.try_into().map_err(|_: ParseFieldError| {
+                    line.slice(i, i + c.len_utf8())
+                        .error(format!("unexpected `{}` in the path", c))
+                })
+            })
+            .collect::<ParseResult<Vec<Field>>>()
+    })?;
+
+    let line_len = rows.iter().map(Vec::len).max().unwrap_or_default();
+    if !rows.first().is_some_and(|row| row.contains(&Field::Vert)) {
+        return Err(Span::new(s).error("expected a `|` on the first line to start from"));
+    }
 
-fn parse_input(s: DayString) -> Path {
-    let line_len: usize = s
-        .lines()
-        .map(str::len)
-        .max()
-        .expect("Lines should not be empty!");
-
-    let v: Vec<Field> = s
-        .lines()
-        .chain(Some("").into_iter())
-        .flat_map(|line| {
-            right_pad(line, line_len, ' ')
-                .chars()
-                .map(|c| c.try_into().unwrap())
-                .collect::<Vec<Field>>()
+    // An empty row at the bottom, so the walker always runs off the path.
+    let n_rows = rows.len() + 1;
+    let v: Vec<Field> = rows
+        .into_iter()
+        .chain(Some(Vec::new()))
+        .flat_map(|mut row| {
+            row.resize(line_len, Field::Empty);
+            row
         })
         .collect();
 
-    let n_rows = v.len()/line_len;
-    ndarray::Array::from_shape_vec([n_rows, line_len], v).unwrap()
+    let path = ndarray::Array::from_shape_vec([n_rows, line_len], v);
+    Ok(path.expect("Rows should be padded to the same length"))
 }
 
 fn solve_day(input: &Path) -> (String, usize) {
@@ -131,16 +131,15 @@ fn solve_day(input: &Path) -> (String, usize) {
     }
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let (sol1, sol2) = solve_day(&parsed);
-    let sol1 = sol1.to_string();
-    let sol2 = sol2.to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(String, usize)> {
+    let parsed = parse_input(s)?;
+    let (sol1, sol2) = helpers::timed(|| solve_day(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(19))
+    let (sol1, sol2) = solve_str(helpers::read_day(19)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -149,19 +148,24 @@ mod tests {
 
     #[test]
     fn test_parse_input() {
-        let input =parse_input(helpers::read_example("19"));
+        let input =parse_input(helpers::read_example("19")).unwrap();
         assert_eq!(input[[0,5]], Field::Vert);
+
+        let err = parse_input("  |\n  A\n  *").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        assert!(parse_input("  -\n  |").is_err());
+        assert!(parse_input("").is_err());
     }
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("19"));
+        let input = parse_input(helpers::read_example("19")).unwrap();
         let (path, _) = solve_day(&input);
         assert_eq!(path, "ABCDEF");
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("19"));
+        let input = parse_input(helpers::read_example("19")).unwrap();
         let (_, n) = solve_day(&input);
         assert_eq!(n, 38);
     }
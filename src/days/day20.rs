@@ -1,32 +1,13 @@
 use std::collections::hash_map;
-use std::ops::Add;
 
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
-
-use nom::bytes::complete::tag;
-use nom::{
-    character::{complete::char, complete::i64},
-    combinator::{map_opt, opt},
-    multi::count,
-    sequence::{delimited, preceded, terminated, tuple},
-};
-use nom::{IResult, Parser};
+use crate::helpers::geom::{Norm, Point3};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
 
 type Input = Vec<Particle>;
 
-#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
-struct Vector {
-    x: i64,
-    y: i64,
-    z: i64,
-}
-
-impl Vector {
-    fn norm(&self) -> i64 {
-        self.x.abs() + self.y.abs() + self.z.abs()
-    }
-}
+type Vector = Point3<i64>;
 
 #[derive(Debug, Hash, Clone)]
 struct Particle {
@@ -35,17 +16,6 @@ struct Particle {
     a: Vector,
 }
 
-impl Add<Vector> for Vector {
-    type Output = Vector;
-
-    fn add(self, rhs: Vector) -> Self::Output {
-        Vector {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
-    }
-}
 impl Particle {
     fn step(&self) -> Particle {
         let v = self.v + self.a;
@@ -55,22 +25,22 @@ impl Particle {
     }
 }
 
-fn vector_parser(s: &str) -> IResult<&str, Vector> {
-    let num_parser = terminated(i64, opt(char(',')));
-    let done = delimited(char('<'), count(num_parser, 3), char('>'));
-    map_opt(done, |v| match &v[..] {
-        &[x, y, z] => Some(Vector { x, y, z }),
-        _ => None,
-    })(s)
+fn vector_parser(s: Span) -> ParseResult<Vector> {
+    let inner = s.trim().strip_prefix("<")?.strip_suffix(">")?;
+    match parse::list(inner, ",")?[..] {
+        [x, y, z] => Ok(Vector::new(x, y, z)),
+        _ => Err(inner.error("expected 3 coordinates")),
+    }
 }
 
-fn particle_parser(s: &str) -> IResult<&str, Particle> {
-    let p_parse = preceded(tag("p="), vector_parser);
-    let v_parse = preceded(tag(", v="), vector_parser);
-    let a_parse = preceded(tag(", a="), vector_parser);
-    tuple((p_parse, v_parse, a_parse))
-        .map(|(p, v, a)| Particle { p, v, a })
-        .parse(s)
+fn particle_parser(s: Span) -> ParseResult<Particle> {
+    let (p, rest) = s.strip_prefix("p=")?.split_once(", v=")?;
+    let (v, a) = rest.split_once(", a=")?;
+    Ok(Particle {
+        p: vector_parser(p)?,
+        v: vector_parser(v)?,
+        a: vector_parser(a)?,
+    })
 }
 
 fn simulate_step(particles: &[Particle]) -> Vec<Particle> {
@@ -96,17 +66,15 @@ fn simulate_step(particles: &[Particle]) -> Vec<Particle> {
         .collect()
 }
 
-fn parse_input(s: DayString) -> Input {
-    s.lines()
-        .map(|line| particle_parser.parse(line).unwrap().1)
-        .collect()
+fn parse_input(s: &str) -> ParseResult<Input> {
+    parse::parse_lines(s, particle_parser)
 }
 
 fn solve_part1(input: &Input) -> usize {
     input
         .iter()
         .enumerate()
-        .min_by(|(_, v1), (_, v2)| v1.a.norm().cmp(&v2.a.norm()))
+        .min_by(|(_, v1), (_, v2)| v1.a.manhattan().cmp(&v2.a.manhattan()))
         .unwrap()
         .0
 }
@@ -130,15 +98,16 @@ fn solve_part2(input: &Input) -> usize {
     }
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> ParseResult<(usize, usize)> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed));
+    let sol2 = helpers::timed(|| solve_part2(&parsed));
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(20))
+    let (sol1, sol2) = solve_str(helpers::read_day(20)).expect("Invalid input");
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
@@ -147,13 +116,13 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("20"));
-        assert_eq!(solve_part1(&input), 2);
+        let input = parse_input(helpers::read_example("20-1")).unwrap();
+        assert_eq!(solve_part1(&input), 0);
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("xxx"));
-        assert_eq!(solve_part2(&input), 3);
+        let input = parse_input(helpers::read_example("20-2")).unwrap();
+        assert_eq!(solve_part2(&input), 1);
     }
 }
@@ -1,10 +1,11 @@
 use crate::helpers;
-use crate::helpers::{DayString, Solution};
+use crate::helpers::parse::{self, ParseResult, Span};
+use crate::helpers::Solution;
+use crate::SolveError;
 
 use std::collections::hash_map;
-use std::str::FromStr;
 
-use ndarray::{Array2, ArrayView2, ArrayViewMut2, Axis, s};
+use ndarray::{Array2, Axis, s};
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 enum Light {
@@ -22,33 +23,43 @@ impl Light {
     }
 }
 
+/// A square pattern like `.#./..#/###`.
+fn parse_grid(span: Span) -> ParseResult<Grid> {
+    let rows: Vec<Span> = span.split("/").collect();
+    let n = rows.len();
+    let mut v = Vec::with_capacity(n * n);
+    for row in rows {
+        if row.as_str().chars().count() != n {
+            return Err(row.error(format!("expected {} lights in `{}`", n, row.as_str())));
+        }
+        for (i, c) in row.as_str().char_indices() {
+            let light = Light::from_char(c).ok_or_else(|| {
+                row.slice(i, i + c.len_utf8())
+                    .error(format!("expected `#` or `.`, found `{}`", c))
+            })?;
+            v.push(light);
+        }
+    }
+    Ok(Array2::from_shape_vec([n, n], v).expect("Grid should be square"))
+}
+
 type Grid = Array2<Light>;
 type Rules = hash_map::HashMap<Grid, Grid>;
 type Rule = (Grid, Grid);
 
-fn parse_rule(s: &str) -> Rule {
-    fn parse_grid(s: &str) -> Grid {
-        let n = s.split('/').next().unwrap().len();
-        let v = s.chars().filter_map(Light::from_char).collect();
-        Array2::from_shape_vec([n, n], v).unwrap()
-    }
-
-    let [left, right]: [&str; 2] = s
-        .splitn(2, " => ")
-        .collect::<Vec<&str>>()
-        .try_into()
-        .expect("Rule should only have 2 components!");
-
-    (parse_grid(left), parse_grid(right))
+fn parse_rule(line: Span) -> ParseResult<Rule> {
+    let (left, right) = line.split_once(" => ")?;
+    Ok((parse_grid(left.trim())?, parse_grid(right.trim())?))
 }
 
-fn parse_input(s: DayString) -> Rules {
-    s.lines().map(parse_rule).collect()
+fn parse_input(s: &str) -> ParseResult<Rules> {
+    parse::parse_lines(s, parse_rule).map(|rules| rules.into_iter().collect())
 }
 
+#[allow(dead_code)]
 fn expand_grid(grid: &Grid, rules: &Rules) -> Grid {
     let n = grid.len_of(Axis(0));
-    let (new_n, old_sq, new_sq) = if n % 2 == 0 {
+    let (new_n, old_sq, new_sq) = if n.is_multiple_of(2) {
         (n / 2 * 3, 2, 3)
     } else {
         (n / 3 * 4, 2, 3)
@@ -72,38 +83,55 @@ fn expand_grid(grid: &Grid, rules: &Rules) -> Grid {
     new_grid
 }
 
-fn solve_part1(input: &Rules) -> usize {
-    unimplemented!();
+fn solve_part1(_input: &Rules) -> Result<usize, SolveError> {
+    Err(SolveError::Unimplemented)
 }
 
-fn solve_part2(input: &Rules) -> usize {
-    unimplemented!();
+fn solve_part2(_input: &Rules) -> Result<usize, SolveError> {
+    Err(SolveError::Unimplemented)
 }
 
-fn solve_string(s: DayString) -> Solution {
-    let parsed = parse_input(s);
-    let sol1 = solve_part1(&parsed).to_string();
-    let sol2 = solve_part2(&parsed).to_string();
-    [sol1, sol2]
+pub fn solve_str(s: &str) -> Result<(usize, usize), SolveError> {
+    let parsed = parse_input(s)?;
+    let sol1 = helpers::timed(|| solve_part1(&parsed))?;
+    let sol2 = helpers::timed(|| solve_part2(&parsed))?;
+    Ok((sol1, sol2))
 }
 
 pub fn solve() -> Solution {
-    solve_string(helpers::read_day(21))
+    let (sol1, sol2) = match solve_str(helpers::read_day(21)) {
+        Ok(solution) => solution,
+        Err(SolveError::Unimplemented) => helpers::unimplemented(),
+        Err(e) => panic!("{}", e),
+    };
+    [sol1.to_string(), sol2.to_string()]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse() {
+        let rules =
+            parse_input("../.# => ##./#../...\n.#./..#/### => #..#/..../..../#..#").unwrap();
+        assert_eq!(rules.len(), 2);
+        let err =
+            parse_input("../.# => ##./#../...\n.#./..#/### => #..#/..x./..../#..#").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 23));
+        assert_eq!(parse_input("../.# => ##./#./...").unwrap_err().column, 14);
+        assert!(parse_input("../.# -> ##./#../...").is_err());
+    }
+
     #[test]
     fn test_part1() {
-        let input = parse_input(helpers::read_example("xxx"));
-        assert_eq!(solve_part1(&input), 2);
+        let input = parse_input(helpers::read_example("xxx")).unwrap();
+        assert_eq!(solve_part1(&input), Ok(2));
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_input(helpers::read_example("xxx"));
-        assert_eq!(solve_part2(&input), 3);
+        let input = parse_input(helpers::read_example("xxx")).unwrap();
+        assert_eq!(solve_part2(&input), Ok(3));
     }
 }
@@ -0,0 +1,342 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Signed integer types usable as coordinates.
+pub trait Coord:
+    Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+}
+
+macro_rules! impl_coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_coord!(i8, i16, i32, i64, i128, isize);
+
+/// Distances from the origin.
+pub trait Norm {
+    type Output;
+
+    /// Sum of the absolute values of the components.
+    fn manhattan(&self) -> Self::Output;
+
+    /// Largest absolute value of the components.
+    fn chebyshev(&self) -> Self::Output;
+}
+
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Point2<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Point2 { x, y }
+    }
+
+    /// Row-major `[y, x]` index for grids, `None` if a component is negative.
+    pub fn into_index(self) -> Option<[usize; 2]>
+    where
+        T: TryInto<usize>,
+    {
+        Some([self.y.try_into().ok()?, self.x.try_into().ok()?])
+    }
+}
+
+impl<T: Coord> Point2<T> {
+    /// The eight points around this one, diagonals included.
+    pub fn surrounding(self) -> [Self; 8] {
+        let (zero, one) = (T::ZERO, T::ONE);
+        [
+            (-one, -one),
+            (zero, -one),
+            (one, -one),
+            (-one, zero),
+            (one, zero),
+            (-one, one),
+            (zero, one),
+            (one, one),
+        ]
+        .map(|(x, y)| self + Point2::new(x, y))
+    }
+}
+
+impl<T> Point3<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Point3 { x, y, z }
+    }
+}
+
+macro_rules! impl_point_ops {
+    ($point:ident { $($field:ident),* }) => {
+        impl<T: Coord> Add for $point<T> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                $point { $($field: self.$field + rhs.$field),* }
+            }
+        }
+
+        impl<T: Coord> Sub for $point<T> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                $point { $($field: self.$field - rhs.$field),* }
+            }
+        }
+
+        impl<T: Coord> Neg for $point<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                $point { $($field: -self.$field),* }
+            }
+        }
+
+        impl<T: Coord> Mul<T> for $point<T> {
+            type Output = Self;
+
+            fn mul(self, rhs: T) -> Self::Output {
+                $point { $($field: self.$field * rhs),* }
+            }
+        }
+
+        impl<T: Coord> AddAssign for $point<T> {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl<T: Coord> SubAssign for $point<T> {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl<T: Coord> Norm for $point<T> {
+            type Output = T;
+
+            fn manhattan(&self) -> T {
+                T::ZERO $(+ self.$field.abs())*
+            }
+
+            fn chebyshev(&self) -> T {
+                [$(self.$field.abs()),*].into_iter().max().unwrap()
+            }
+        }
+    };
+}
+
+impl_point_ops!(Point2 { x, y });
+impl_point_ops!(Point3 { x, y, z });
+
+/// Grid directions. `y` grows downwards, as in row-major grids.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Dir {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Dir {
+    pub fn turn_left(self) -> Dir {
+        match self {
+            Dir::Up => Dir::Left,
+            Dir::Left => Dir::Down,
+            Dir::Down => Dir::Right,
+            Dir::Right => Dir::Up,
+        }
+    }
+
+    pub fn turn_right(self) -> Dir {
+        match self {
+            Dir::Up => Dir::Right,
+            Dir::Right => Dir::Down,
+            Dir::Down => Dir::Left,
+            Dir::Left => Dir::Up,
+        }
+    }
+
+    pub fn delta<T: Coord>(self) -> Point2<T> {
+        let (zero, one) = (T::ZERO, T::ONE);
+        match self {
+            Dir::Up => Point2::new(zero, -one),
+            Dir::Right => Point2::new(one, zero),
+            Dir::Down => Point2::new(zero, one),
+            Dir::Left => Point2::new(-one, zero),
+        }
+    }
+}
+
+impl<T: Coord> Add<Dir> for Point2<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Dir) -> Self::Output {
+        self + rhs.delta()
+    }
+}
+
+impl<T: Coord> AddAssign<Dir> for Point2<T> {
+    fn add_assign(&mut self, rhs: Dir) {
+        *self = *self + rhs;
+    }
+}
+
+/// Hexagon in cube coordinates, `q + r + s == 0` always holds.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct Hex<T> {
+    pub q: T,
+    pub r: T,
+    pub s: T,
+}
+
+/// Neighbours of a flat-topped hexagon.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum HexDir {
+    N,
+    NE,
+    SE,
+    S,
+    SW,
+    NW,
+}
+
+impl<T: Coord> Hex<T> {
+    /// Number of steps needed to reach the origin.
+    pub fn distance(&self) -> T {
+        self.chebyshev()
+    }
+}
+
+impl<T: Coord> Norm for Hex<T> {
+    type Output = T;
+
+    fn manhattan(&self) -> T {
+        self.q.abs() + self.r.abs() + self.s.abs()
+    }
+
+    fn chebyshev(&self) -> T {
+        self.q.abs().max(self.r.abs()).max(self.s.abs())
+    }
+}
+
+impl HexDir {
+    pub fn delta<T: Coord>(self) -> Hex<T> {
+        let (zero, one) = (T::ZERO, T::ONE);
+        let (q, r, s) = match self {
+            HexDir::N => (zero, -one, one),
+            HexDir::NE => (one, -one, zero),
+            HexDir::SE => (one, zero, -one),
+            HexDir::S => (zero, one, -one),
+            HexDir::SW => (-one, one, zero),
+            HexDir::NW => (-one, zero, one),
+        };
+        Hex { q, r, s }
+    }
+}
+
+impl FromStr for HexDir {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "n" => Ok(HexDir::N),
+            "ne" => Ok(HexDir::NE),
+            "se" => Ok(HexDir::SE),
+            "s" => Ok(HexDir::S),
+            "sw" => Ok(HexDir::SW),
+            "nw" => Ok(HexDir::NW),
+            _ => Err(()),
+        }
+    }
+}
+
+impl<T: Coord> Add for Hex<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Hex {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+            s: self.s + rhs.s,
+        }
+    }
+}
+
+impl<T: Coord> AddAssign<HexDir> for Hex<T> {
+    fn add_assign(&mut self, rhs: HexDir) {
+        *self = *self + rhs.delta();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_ops() {
+        let p = Point2::new(3, -4);
+        let q = Point2::new(-1, 2);
+        assert_eq!(p + q, Point2::new(2, -2));
+        assert_eq!(p - q, Point2::new(4, -6));
+        assert_eq!(-p * 2, Point2::new(-6, 8));
+        assert_eq!(p.manhattan(), 7);
+        assert_eq!(p.chebyshev(), 4);
+
+        let v = Point3::new(1i64, -5, 2);
+        assert_eq!(v.manhattan(), 8);
+        assert_eq!(v.chebyshev(), 5);
+    }
+
+    #[test]
+    fn test_dir() {
+        let mut p: Point2<i32> = Point2::default();
+        let mut dir = Dir::Up;
+        for _ in 0..4 {
+            p += dir;
+            dir = dir.turn_right();
+        }
+        assert_eq!(p, Point2::default());
+        assert_eq!(Dir::Left.turn_left(), Dir::Down);
+        assert_eq!(Point2::new(-1isize, 2).into_index(), None);
+        assert_eq!(Point2::new(1isize, 2).into_index(), Some([2, 1]));
+
+        let around = Point2::new(0, 0).surrounding();
+        assert!(around.iter().all(|p| p.chebyshev() == 1));
+        assert_eq!(around.iter().map(|p| p.manhattan()).sum::<i32>(), 12);
+    }
+
+    #[test]
+    fn test_hex() {
+        let mut hex: Hex<i32> = Hex::default();
+        for dir in [HexDir::NE, HexDir::NE, HexDir::S, HexDir::S] {
+            hex += dir;
+        }
+        assert_eq!(hex.distance(), 2);
+        assert_eq!(hex.q + hex.r + hex.s, 0);
+    }
+}
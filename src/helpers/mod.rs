@@ -0,0 +1,82 @@
+pub mod geom;
+pub mod parse;
+pub mod trace;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+pub type Solution = [String; 2];
+
+pub type DayString = &'static str;
+
+/// Directory and file prefix `read_day` looks in, `inputs/dayNN.in` unless
+/// the runner says otherwise.
+static INPUT_SOURCE: OnceLock<(PathBuf, &'static str)> = OnceLock::new();
+
+pub fn set_input_source(dir: PathBuf, prefix: &'static str) {
+    INPUT_SOURCE
+        .set((dir, prefix))
+        .expect("Input source should only be set once");
+}
+
+thread_local! {
+    static PART_TIMES: RefCell<Option<Vec<Duration>>> = const { RefCell::new(None) };
+}
+
+/// Runs one part of a day and notes how long it took for the run summary.
+/// Days solving both parts at once wrap the combined call instead.
+pub fn timed<T>(part: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = part();
+    PART_TIMES.with(|times| {
+        if let Some(times) = times.borrow_mut().as_mut() {
+            times.push(start.elapsed())
+        }
+    });
+    result
+}
+
+/// Runs `f` and returns the part times it recorded through `timed`.
+/// Outside of this, `timed` records nothing.
+pub fn collect_part_times<T>(f: impl FnOnce() -> T) -> (T, Vec<Duration>) {
+    PART_TIMES.set(Some(Vec::new()));
+    let result = f();
+    let times = PART_TIMES.take().unwrap_or_default();
+    (result, times)
+}
+
+/// Panic payload of `unimplemented`, so the runner can tell days that are
+/// not written yet from days that fail.
+#[derive(Debug)]
+pub struct Unimplemented;
+
+/// Marks a day or part that is not written yet.
+pub fn unimplemented() -> ! {
+    std::panic::panic_any(Unimplemented)
+}
+
+pub fn read_day(n: u8) -> DayString {
+    let (dir, prefix) = INPUT_SOURCE.get_or_init(|| (PathBuf::from("inputs"), "day"));
+    let path = dir.join(format!("{}{:02}.in", prefix, n));
+    let error_msg = format!("Unable to access {}", path.display());
+    Box::leak(
+        std::fs::read_to_string(path)
+            .expect(&error_msg)
+            .into_boxed_str(),
+    )
+    .trim_end()
+}
+
+#[cfg(test)]
+pub fn read_example<T: std::fmt::Display>(n: T) -> DayString {
+    let path = format!("examples/ex{}.in", n);
+    let error_msg = format!("Unable to access {}", path);
+    Box::leak(
+        std::fs::read_to_string(path)
+            .expect(&error_msg)
+            .into_boxed_str(),
+    )
+    .trim_end()
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// Parse failure with a 1-based line and column into the original input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.msg
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Piece of the input that remembers where it came from, so errors can
+/// point back to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Span<'a> {
+    src: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Span {
+            src,
+            start: 0,
+            end: src.len(),
+        }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        &self.src[self.start..self.end]
+    }
+
+    fn sub(&self, text: &'a str) -> Span<'a> {
+        let start = text.as_ptr() as usize - self.src.as_ptr() as usize;
+        Span {
+            src: self.src,
+            start,
+            end: start + text.len(),
+        }
+    }
+
+    /// Bytes `start..end` of this span.
+    pub fn slice(&self, start: usize, end: usize) -> Span<'a> {
+        self.sub(&self.as_str()[start..end])
+    }
+
+    /// 1-based line the span starts on. Counts from the start of the input,
+    /// so only for errors.
+    fn line(&self) -> usize {
+        self.src[..self.start].matches('\n').count() + 1
+    }
+
+    pub fn error(&self, msg: impl Into<String>) -> ParseError {
+        let before = &self.src[..self.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            line: self.line(),
+            column: before[line_start..].chars().count() + 1,
+            msg: msg.into(),
+        }
+    }
+
+    pub fn parse<T: FromStr>(&self) -> ParseResult<T> {
+        self.as_str().parse().map_err(|_| {
+            let type_name = std::any::type_name::<T>().rsplit("::").next().unwrap();
+            self.error(format!("cannot parse `{}` as {}", self.as_str(), type_name))
+        })
+    }
+
+    pub fn trim(&self) -> Span<'a> {
+        self.sub(self.as_str().trim())
+    }
+
+    pub fn split(&self, sep: &'a str) -> impl Iterator<Item = Span<'a>> + 'a {
+        let this = *self;
+        self.as_str().split(sep).map(move |s| this.sub(s).trim())
+    }
+
+    pub fn split_whitespace(&self) -> impl Iterator<Item = Span<'a>> + 'a {
+        let this = *self;
+        self.as_str().split_whitespace().map(move |s| this.sub(s))
+    }
+
+    pub fn split_once(&self, sep: &str) -> ParseResult<(Span<'a>, Span<'a>)> {
+        let (a, b) = self
+            .as_str()
+            .split_once(sep)
+            .ok_or_else(|| self.error(format!("expected `{}` in `{}`", sep, self.as_str())))?;
+        Ok((self.sub(a), self.sub(b)))
+    }
+
+    pub fn strip_prefix(&self, prefix: &str) -> ParseResult<Span<'a>> {
+        self.as_str()
+            .strip_prefix(prefix)
+            .map(|s| self.sub(s))
+            .ok_or_else(|| self.error(format!("expected `{}`", prefix)))
+    }
+
+    pub fn strip_suffix(&self, suffix: &str) -> ParseResult<Span<'a>> {
+        self.as_str()
+            .strip_suffix(suffix)
+            .map(|s| self.sub(s))
+            .ok_or_else(|| {
+                self.error(format!(
+                    "expected `{}` at the end of `{}`",
+                    suffix,
+                    self.as_str()
+                ))
+            })
+    }
+}
+
+pub fn lines(s: &str) -> impl Iterator<Item = Span<'_>> {
+    let span = Span::new(s);
+    s.lines().map(move |line| span.sub(line))
+}
+
+/// Applies `f` to every line, stopping at the first error.
+pub fn parse_lines<'a, T, F>(s: &'a str, f: F) -> ParseResult<Vec<T>>
+where
+    F: FnMut(Span<'a>) -> ParseResult<T>,
+{
+    lines(s).map(f).collect()
+}
+
+/// Tokens separated by `sep`, e.g. `3,4,1,5` or `ne,ne,s`.
+pub fn list<T: FromStr>(span: Span, sep: &str) -> ParseResult<Vec<T>> {
+    span.split(sep).map(|tok| tok.parse()).collect()
+}
+
+/// Tokens separated by any amount of whitespace, including newlines.
+pub fn words<T: FromStr>(span: Span) -> ParseResult<Vec<T>> {
+    span.split_whitespace().map(|tok| tok.parse()).collect()
+}
+
+/// A run of decimal digits such as `91212129`.
+pub fn digits(span: Span) -> ParseResult<Vec<u32>> {
+    span.as_str()
+        .char_indices()
+        .map(|(i, c)| {
+            c.to_digit(10).ok_or_else(|| {
+                let at = span.sub(&span.as_str()[i..i + c.len_utf8()]);
+                at.error(format!("expected a digit, found `{}`", c))
+            })
+        })
+        .collect()
+}
+
+/// Lines of the shape `key: value`.
+pub fn key_value<K: FromStr, V: FromStr>(span: Span, sep: &str) -> ParseResult<(K, V)> {
+    let (key, value) = span.split_once(sep)?;
+    Ok((key.trim().parse()?, value.trim().parse()?))
+}
+
+/// Lines of the shape `a <-> b, c` with the arrow given by `arrow`.
+pub fn adjacency<T: FromStr>(span: Span, arrow: &str) -> ParseResult<(T, Vec<T>)> {
+    let (node, neighbours) = span.split_once(arrow)?;
+    Ok((node.trim().parse()?, list(neighbours.trim(), ",")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list() {
+        assert_eq!(list::<u8>(Span::new("3, 4,1"), ","), Ok(vec![3, 4, 1]));
+        let err = list::<u8>(Span::new("3,x,1"), ",").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 3));
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words::<i32>(Span::new("0\n3  -1")), Ok(vec![0, 3, -1]));
+        let err = words::<i32>(Span::new("0\n3 one")).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits(Span::new("1122")), Ok(vec![1, 1, 2, 2]));
+        assert_eq!(digits(Span::new("11a2")).unwrap_err().column, 3);
+    }
+
+    #[test]
+    fn test_lines() {
+        let input = "0: 3\n1: 2\n4 4";
+        let err = parse_lines(input, |ln| key_value::<u32, u32>(ln, ":")).unwrap_err();
+        assert_eq!(err.line, 3);
+
+        let adj = parse_lines("0 <-> 2\n2 <-> 0, 3, 4", |ln| adjacency::<u32>(ln, "<->"));
+        assert_eq!(adj, Ok(vec![(0, vec![2]), (2, vec![0, 3, 4])]));
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("Unknown trace level `{}`", s)),
+        }
+    }
+}
+
+/// Traced targets with their most verbose level, written like
+/// `day18` or `day06=info,day14`. A bare target traces everything, `all`
+/// matches every day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter(Vec<(String, Level)>);
+
+impl Filter {
+    pub fn level(&self, target: &str) -> Option<Level> {
+        self.0
+            .iter()
+            .filter(|(t, _)| t == target || t == "all")
+            .map(|&(_, level)| level)
+            .max()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| match t.split_once('=') {
+                Some((target, level)) => Ok((target.to_string(), level.parse()?)),
+                None => Ok((t.to_string(), Level::Trace)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Filter)
+    }
+}
+
+struct Tracer {
+    filter: Filter,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+static TRACER: OnceLock<Tracer> = OnceLock::new();
+
+/// Turns tracing on, writing to `file` or to stderr.
+pub fn init(filter: Filter, file: Option<&Path>) -> io::Result<()> {
+    let out: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(LineWriter::new(File::create(path)?)),
+        None => Box::new(io::stderr()),
+    };
+    if TRACER.set(Tracer { filter, out: Mutex::new(out) }).is_err() {
+        panic!("Tracing should only be initialised once");
+    }
+    Ok(())
+}
+
+pub fn enabled(target: &str, level: Level) -> bool {
+    TRACER
+        .get()
+        .is_some_and(|t| t.filter.level(target).is_some_and(|max| level <= max))
+}
+
+/// Day a module traces as, `day18` for `aoc2017::days::day18`.
+pub fn target(module_path: &'static str) -> &'static str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+pub fn write(target: &str, level: Level, step: u64, args: fmt::Arguments) {
+    if let Some(tracer) = TRACER.get() {
+        let mut out = tracer.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "[{} {:<5} #{}] {}", target, level, step, args);
+    }
+}
+
+/// Emits an event for the calling day if `--trace` asks for it, e.g.
+/// `trace!(Debug, step, "banks {:?}", banks)`. The arguments are only
+/// evaluated when the event is written.
+#[macro_export]
+macro_rules! trace {
+    ($level:ident, $step:expr, $($arg:tt)+) => {{
+        use $crate::helpers::trace;
+        let target = trace::target(module_path!());
+        if trace::enabled(target, trace::Level::$level) {
+            trace::write(target, trace::Level::$level, $step as u64, format_args!($($arg)+));
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let filter: Filter = "day06=info, day18".parse().unwrap();
+        assert_eq!(filter.level("day06"), Some(Level::Info));
+        assert_eq!(filter.level("day18"), Some(Level::Trace));
+        assert_eq!(filter.level("day14"), None);
+
+        let filter: Filter = "all=debug,day14".parse().unwrap();
+        assert_eq!(filter.level("day05"), Some(Level::Debug));
+        assert_eq!(filter.level("day14"), Some(Level::Trace));
+        assert!("day18=loud".parse::<Filter>().is_err());
+
+        assert_eq!(target("aoc2017::days::day18"), "day18");
+    }
+}
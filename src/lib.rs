@@ -0,0 +1,178 @@
+pub mod days;
+pub mod helpers;
+
+use std::fmt;
+
+use days::*;
+use helpers::parse::ParseError;
+
+/// Answer to one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Int(i128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! impl_int_answer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Int(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_int_answer!(u16, u32, u64, i32, usize, i128);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    UnknownDay(u8),
+    Parse(ParseError),
+    /// The input is valid but has no answer, e.g. a day07 tower without an
+    /// imbalance.
+    Solve(String),
+    Unimplemented,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::UnknownDay(day) => write!(f, "no solver for day {}", day),
+            SolveError::Parse(e) => write!(f, "invalid input: {}", e),
+            SolveError::Solve(msg) => write!(f, "no answer: {}", msg),
+            SolveError::Unimplemented => write!(f, "not implemented"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<ParseError> for SolveError {
+    fn from(e: ParseError) -> Self {
+        SolveError::Parse(e)
+    }
+}
+
+fn answers<A, B, E>(solution: Result<(A, B), E>) -> Result<[Answer; 2], SolveError>
+where
+    A: Into<Answer>,
+    B: Into<Answer>,
+    E: Into<SolveError>,
+{
+    let (a, b) = solution.map_err(Into::into)?;
+    Ok([a.into(), b.into()])
+}
+
+/// Solves `day` for the puzzle input `input`, without touching the
+/// filesystem. Use the `solve_str` of the day's module for typed answers.
+pub fn solve_str(day: u8, input: &str) -> Result<[Answer; 2], SolveError> {
+    let input = input.trim_end();
+    match day {
+        1  => answers(day01::solve_str(input)),
+        2  => answers(day02::solve_str(input)),
+        3  => answers(day03::solve_str(input)),
+        4  => answers(day04::solve_str(input)),
+        5  => answers(day05::solve_str(input)),
+        6  => answers(day06::solve_str(input)),
+        7  => answers(day07::solve_str(input)),
+        8  => answers(day08::solve_str(input)),
+        9  => answers(day09::solve_str(input)),
+        10 => answers(day10::solve_str(input)),
+        11 => answers(day11::solve_str(input)),
+        12 => answers(day12::solve_str(input)),
+        13 => answers(day13::solve_str(input)),
+        14 => answers(day14::solve_str(input)),
+        15 => answers(day15::solve_str(input)),
+        16 => answers(day16::solve_str(input)),
+        17 => answers(day17::solve_str(input)),
+        18 => answers(day18::solve_str(input)),
+        19 => answers(day19::solve_str(input)),
+        20 => answers(day20::solve_str(input)),
+        21 => answers(day21::solve_str(input)),
+        _ => Err(SolveError::UnknownDay(day)),
+    }
+}
+
+/// Details a day can show beyond its answers, `None` for days without any.
+pub fn report_str(day: u8, input: &str) -> Result<Option<String>, SolveError> {
+    let input = input.trim_end();
+    let report = match day {
+        2 => day02::report_str(input)?,
+        7 => day07::report_str(input)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(report))
+}
+
+/// A picture of the input a day can draw, as the extension of the file it
+/// belongs in and the file's contents. `None` for days without any.
+pub fn export_str(day: u8, input: &str) -> Result<Option<(&'static str, String)>, SolveError> {
+    let input = input.trim_end();
+    let export = match day {
+        6 => ("csv", day06::export_str(input)?),
+        7 => ("dot", day07::Tower::parse(input)?.to_dot()),
+        8 => ("csv", day08::Program::parse(input)?.history()?.to_csv()),
+        _ => return Ok(None),
+    };
+    Ok(Some(export))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_str() {
+        let answers = solve_str(1, "1122\n").unwrap();
+        assert_eq!(answers, [Answer::Int(3), Answer::Int(0)]);
+
+        let answers = solve_str(16, "s1,x3/4,pe/b").unwrap();
+        assert_eq!(answers[0].to_string(), "paedcbfghijklmno");
+
+        assert_eq!(solve_str(30, ""), Err(SolveError::UnknownDay(30)));
+        assert!(matches!(solve_str(5, "0 3 x"), Err(SolveError::Parse(_))));
+        assert_eq!(solve_str(21, "../.# => ##./#../..."), Err(SolveError::Unimplemented));
+    }
+
+    #[test]
+    fn test_export_str() {
+        let (ext, csv) = export_str(6, "0 2 7 0\n").unwrap().unwrap();
+        assert_eq!(ext, "csv");
+        assert!(csv.starts_with("step,bank0,bank1,bank2,bank3\n0,0,2,7,0\n1,2,4,1,2\n"));
+        assert!(csv.ends_with("5,2,4,1,2\n"));
+
+        let (ext, dot) = export_str(7, helpers::read_example("07")).unwrap().unwrap();
+        assert_eq!(ext, "dot");
+        assert!(dot.starts_with("digraph tower {"));
+
+        let (ext, csv) = export_str(8, "a inc 1 if b < 5").unwrap().unwrap();
+        assert_eq!((ext, csv.as_str()), ("csv", "step,line,a,a_max,b,b_max\n1,1,1,1,0,0"));
+
+        assert_eq!(export_str(1, "1122"), Ok(None));
+        assert!(matches!(export_str(6, "0 x"), Err(SolveError::Parse(_))));
+    }
+}
@@ -1,38 +1,257 @@
-mod days;
-mod helpers;
+mod config;
+mod runner;
+mod summary;
 
-use crate::helpers::Solution;
-use days::*;
+use std::path::Path;
 
-fn all_days() -> Vec<u8> {
-    (1..26).collect()
-}
+use crate::config::{Answers, Config, Format};
+use crate::runner::{DayResult, Outcome, Variations};
+use crate::summary::Summary;
+use aoc2017::days::*;
+use aoc2017::helpers::{self, trace, Solution};
 
 fn main() {
     let args: Vec<String> = std::env::args().skip(1).collect();
 
-    let day_string: Option<u8> = args.get(0).and_then(|x| x.parse().ok());
-    let days: Vec<u8> = day_string.map_or_else(all_days, |x| vec![x]);
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", config::USAGE);
+        return;
+    }
+
+    let config = Config::load(&args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, config::USAGE);
+        std::process::exit(2)
+    });
+
+    if config.use_examples {
+        helpers::set_input_source(config.example_dir.clone(), "ex");
+    } else {
+        helpers::set_input_source(config.input_dir.clone(), "day");
+    }
+
+    if let Some(filter) = config.trace.clone() {
+        if let Err(e) = trace::init(filter, config.trace_file.as_deref()) {
+            eprintln!("Unable to start tracing: {}", e);
+            std::process::exit(2)
+        }
+    }
+
+    let answers = config
+        .answers
+        .as_deref()
+        .map(config::load_answers)
+        .transpose()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2)
+        })
+        .unwrap_or_default();
+
+    let jobs = config
+        .days
+        .iter()
+        .filter_map(|&day| get_day_solver(day).map(|solve| (day, solve)))
+        .collect();
+    let missing: Vec<u8> = config
+        .days
+        .iter()
+        .copied()
+        .filter(|&day| get_day_solver(day).is_none())
+        .collect();
+
+    if let Some(runs) = config.determinism {
+        let variations =
+            runner::check_determinism(jobs, runs, config.load, config.threads, config.timeout);
+        print_determinism(&config, runs, &variations);
+        if !variations.iter().all(Variations::is_deterministic) {
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let start_time = std::time::Instant::now();
-    days.into_iter().for_each(run_day);
-    let end_time = start_time.elapsed().as_millis();
+    let mut results = Vec::new();
+    runner::run_days(jobs, config.threads, config.timeout, |result| {
+        print_day(&config, &answers, &result);
+        if config.report && matches!(result.outcome, Outcome::Solved(_)) {
+            print_report(&config, result.day);
+        }
+        if let (Some(dir), Outcome::Solved(_)) = (&config.export_dir, &result.outcome) {
+            export(&config, dir, result.day);
+        }
+        results.push(result);
+    });
+    let elapsed = start_time.elapsed();
+
+    if config.summary {
+        Summary::new(&results, &missing, elapsed).print(config.format);
+        return;
+    }
+
+    let end_time = elapsed.as_millis();
+    match config.format {
+        Format::Text => {
+            println!("\n=============");
+            println!("Overall Time: {:.4} ms", end_time);
+        }
+        Format::Json => println!("{{\"overall_ms\":{}}}", end_time),
+    }
+}
 
-    println!("\n=============");
-    println!("Overall Time: {:.4} ms", end_time);
+fn check(answers: &Answers, day: u8, part: usize, value: &str) -> Option<bool> {
+    answers.get(&day).map(|expected| expected[part] == value)
 }
 
-fn run_day(day: u8) {
-    let solve = get_day_solver(day);
-    let Some(solve) = solve else { return };
-    let start = std::time::Instant::now();
-    let [p1, p2] = solve();
-    let elapsed_ms = start.elapsed().as_nanos();
+fn print_day(config: &Config, answers: &Answers, result: &DayResult) {
+    let day = result.day;
+    let elapsed_ms = result.elapsed.as_nanos();
 
-    println!("\n=== Day {:02} ===", day);
-    println!("  · Part 1: {}", p1);
-    println!("  · Part 2: {}", p2);
-    println!("  · Elapsed: {:.4} ns", elapsed_ms);
+    match config.format {
+        Format::Text => {
+            println!("\n=== Day {:02} ===", day);
+            match &result.outcome {
+                Outcome::Solved(solution) => {
+                    for (part, value) in solution.iter().enumerate() {
+                        let mark = match check(answers, day, part, value) {
+                            Some(true) => " ✓".to_string(),
+                            Some(false) => format!(" ✗ (expected {})", answers[&day][part]),
+                            None => "".to_string(),
+                        };
+                        println!("  · Part {}: {}{}", part + 1, value, mark);
+                    }
+                    println!("  · Elapsed: {:.4} ns", elapsed_ms);
+                }
+                Outcome::Failed(msg) => println!("  · Failed: {}", msg),
+                Outcome::Unimplemented => println!("  · Not implemented"),
+                Outcome::TimedOut => println!("  · Timed out after {:?}", result.elapsed),
+            }
+        }
+        Format::Json => {
+            let mut fields = vec![format!("\"day\":{}", day)];
+            match &result.outcome {
+                Outcome::Solved(solution) => {
+                    fields.push("\"status\":\"solved\"".to_string());
+                    for (part, value) in solution.iter().enumerate() {
+                        fields.push(format!("\"part{}\":{}", part + 1, json_string(value)));
+                        if let Some(ok) = check(answers, day, part, value) {
+                            fields.push(format!("\"part{}_correct\":{}", part + 1, ok));
+                        }
+                    }
+                }
+                Outcome::Failed(msg) => {
+                    fields.push("\"status\":\"failed\"".to_string());
+                    fields.push(format!("\"error\":{}", json_string(msg)));
+                }
+                Outcome::Unimplemented => {
+                    fields.push("\"status\":\"unimplemented\"".to_string())
+                }
+                Outcome::TimedOut => fields.push("\"status\":\"timeout\"".to_string()),
+            }
+            fields.push(format!("\"elapsed_ns\":{}", elapsed_ms));
+            println!("{{{}}}", fields.join(","));
+        }
+    }
+}
+
+fn print_report(config: &Config, day: u8) {
+    let report = match aoc2017::report_str(day, helpers::read_day(day)) {
+        Ok(Some(report)) => report,
+        Ok(None) => return,
+        Err(e) => format!("Report failed: {}", e),
+    };
+    match config.format {
+        Format::Text => {
+            println!("  · Report:");
+            for line in report.lines() {
+                match line {
+                    "" => println!(),
+                    line => println!("    {}", line),
+                }
+            }
+        }
+        Format::Json => println!("{{\"day\":{},\"report\":{}}}", day, json_string(&report)),
+    }
+}
+
+fn export(config: &Config, dir: &Path, day: u8) {
+    let written = match aoc2017::export_str(day, helpers::read_day(day)) {
+        Ok(Some((extension, contents))) => {
+            let path = dir.join(format!("day{:02}.{}", day, extension));
+            std::fs::create_dir_all(dir)
+                .and_then(|_| std::fs::write(&path, contents))
+                .map(|_| path.display().to_string())
+                .map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+        }
+        Ok(None) => return,
+        Err(e) => Err(format!("Export failed: {}", e)),
+    };
+    match (config.format, written) {
+        (Format::Text, Ok(path)) => println!("  · Exported: {}", path),
+        (Format::Text, Err(e)) => println!("  · {}", e),
+        (Format::Json, Ok(path)) => println!("{{\"day\":{},\"export\":{}}}", day, json_string(&path)),
+        (Format::Json, Err(e)) => println!("{{\"day\":{},\"export_error\":{}}}", day, json_string(&e)),
+    }
+}
+
+fn describe(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Solved([p1, p2]) => format!("{} / {}", p1, p2),
+        Outcome::Failed(msg) => format!("failed: {}", msg),
+        Outcome::Unimplemented => "not implemented".to_string(),
+        Outcome::TimedOut => "timed out".to_string(),
+    }
+}
+
+fn print_determinism(config: &Config, runs: usize, variations: &[Variations]) {
+    match config.format {
+        Format::Text => {
+            println!("\n=== Determinism: {} runs, {} load threads ===", runs, config.load);
+            for v in variations {
+                if v.is_deterministic() {
+                    println!("Day {:02}: ok", v.day);
+                    continue;
+                }
+                println!("Day {:02}: {} different outcomes", v.day, v.outcomes.len());
+                for (outcome, count) in &v.outcomes {
+                    println!("  · {:>3}× {}", count, describe(outcome));
+                }
+            }
+            let n_bad = variations.iter().filter(|v| !v.is_deterministic()).count();
+            println!("\n{} of {} days nondeterministic", n_bad, variations.len());
+        }
+        Format::Json => {
+            for v in variations {
+                let outcomes: Vec<String> = v
+                    .outcomes
+                    .iter()
+                    .map(|(outcome, count)| {
+                        format!("{{\"runs\":{},\"outcome\":{}}}", count, json_string(&describe(outcome)))
+                    })
+                    .collect();
+                println!(
+                    "{{\"day\":{},\"deterministic\":{},\"outcomes\":[{}]}}",
+                    v.day,
+                    v.is_deterministic(),
+                    outcomes.join(",")
+                );
+            }
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
 }
 
 fn get_day_solver(day: u8) -> Option<fn() -> Solution> {
@@ -0,0 +1,268 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Once};
+use std::panic;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use aoc2017::helpers::{self, Solution};
+
+pub type Solver = fn() -> Solution;
+
+/// Solvers recurse deeply in places, give them as much stack as `main` has.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Solved(Solution),
+    Failed(String),
+    Unimplemented,
+    TimedOut,
+}
+
+#[derive(Debug)]
+pub struct DayResult {
+    pub day: u8,
+    pub outcome: Outcome,
+    pub elapsed: Duration,
+    /// Durations of the parts that finished, see `helpers::timed`.
+    pub parts: Vec<Duration>,
+}
+
+/// Keeps the default panic output for everything but unimplemented days,
+/// which are expected and reported as such.
+fn quiet_unimplemented() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !info.payload().is::<helpers::Unimplemented>() {
+                default(info)
+            }
+        }));
+    });
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return s.to_string();
+    }
+    if let Some(s) = payload.downcast_ref::<String>() {
+        return s.clone();
+    }
+    "unknown panic".to_string()
+}
+
+/// Solves a day on its own thread. A day that times out is abandoned, its
+/// thread keeps running until the process exits.
+pub fn run_one(day: u8, solve: Solver, timeout: Option<Duration>) -> DayResult {
+    quiet_unimplemented();
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name(format!("day{:02}", day))
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let start = Instant::now();
+            let (solution, parts) = helpers::collect_part_times(|| panic::catch_unwind(solve));
+            let elapsed = start.elapsed();
+            let solution =
+                solution.map_err(|payload| match payload.is::<helpers::Unimplemented>() {
+                    true => None,
+                    false => Some(panic_message(payload)),
+                });
+            let _ = tx.send((solution, elapsed, parts));
+        })
+        .expect("Unable to spawn solver thread");
+
+    let received = match timeout {
+        Some(timeout) => rx.recv_timeout(timeout),
+        None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+    };
+
+    let (outcome, elapsed, parts) = match received {
+        Ok((Ok(solution), elapsed, parts)) => (Outcome::Solved(solution), elapsed, parts),
+        Ok((Err(None), elapsed, parts)) => (Outcome::Unimplemented, elapsed, parts),
+        Ok((Err(Some(msg)), elapsed, parts)) => (Outcome::Failed(msg), elapsed, parts),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            (Outcome::TimedOut, timeout.unwrap_or_default(), Vec::new())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => (
+            Outcome::Failed("solver thread died".to_string()),
+            Duration::ZERO,
+            Vec::new(),
+        ),
+    };
+
+    DayResult {
+        day,
+        outcome,
+        elapsed,
+        parts,
+    }
+}
+
+/// Solves the days on `threads` workers and hands the results to `report`
+/// in the order of `jobs`.
+pub fn run_days<F>(
+    jobs: Vec<(u8, Solver)>,
+    threads: usize,
+    timeout: Option<Duration>,
+    mut report: F,
+) where
+    F: FnMut(DayResult),
+{
+    let n_jobs = jobs.len();
+    let jobs = Arc::new(jobs);
+    let next_job = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..threads.min(n_jobs) {
+        let jobs = Arc::clone(&jobs);
+        let next_job = Arc::clone(&next_job);
+        let tx = tx.clone();
+        thread::spawn(move || loop {
+            let i = next_job.fetch_add(1, Ordering::Relaxed);
+            let Some(&(day, solve)) = jobs.get(i) else {
+                break;
+            };
+            if tx.send((i, run_one(day, solve, timeout))).is_err() {
+                break;
+            }
+        });
+    }
+    drop(tx);
+
+    let mut pending = BTreeMap::new();
+    let mut next_report = 0;
+    for (i, result) in rx {
+        pending.insert(i, result);
+        while let Some(result) = pending.remove(&next_report) {
+            report(result);
+            next_report += 1;
+        }
+    }
+}
+
+/// Every distinct outcome a day produced over repeated runs, with the
+/// number of runs that produced it.
+#[derive(Debug)]
+pub struct Variations {
+    pub day: u8,
+    pub outcomes: Vec<(Outcome, usize)>,
+}
+
+impl Variations {
+    pub fn is_deterministic(&self) -> bool {
+        self.outcomes.len() == 1
+    }
+}
+
+/// Threads spinning on their own cores until dropped, to shake out solvers
+/// that depend on scheduling.
+struct BusyLoad {
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl BusyLoad {
+    fn start(n_threads: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handles = (0..n_threads)
+            .map(|_| {
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || {
+                    let mut x = 0u64;
+                    while !stop.load(Ordering::Relaxed) {
+                        x = std::hint::black_box(
+                            x.wrapping_mul(6364136223846793005).wrapping_add(1),
+                        );
+                    }
+                })
+            })
+            .collect();
+        BusyLoad { stop, handles }
+    }
+}
+
+impl Drop for BusyLoad {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handles
+            .drain(..)
+            .for_each(|h| h.join().unwrap_or_default());
+    }
+}
+
+/// Solves every day `runs` times, with `load` busy threads running
+/// alongside, and collects the outcomes per day.
+pub fn check_determinism(
+    jobs: Vec<(u8, Solver)>,
+    runs: usize,
+    load: usize,
+    threads: usize,
+    timeout: Option<Duration>,
+) -> Vec<Variations> {
+    let repeated = jobs
+        .iter()
+        .flat_map(|&job| std::iter::repeat_n(job, runs))
+        .collect();
+
+    let _load = BusyLoad::start(load);
+    let mut variations: Vec<Variations> = Vec::new();
+    run_days(repeated, threads, timeout, |result| {
+        if variations.last().is_none_or(|v| v.day != result.day) {
+            variations.push(Variations {
+                day: result.day,
+                outcomes: Vec::new(),
+            });
+        }
+        let outcomes = &mut variations.last_mut().unwrap().outcomes;
+        match outcomes.iter_mut().find(|(o, _)| *o == result.outcome) {
+            Some((_, count)) => *count += 1,
+            None => outcomes.push((result.outcome, 1)),
+        }
+    });
+    variations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn steady() -> Solution {
+        ["1".to_string(), "2".to_string()]
+    }
+
+    fn flaky() -> Solution {
+        let n = CALLS.fetch_add(1, Ordering::Relaxed);
+        [(n % 2).to_string(), "2".to_string()]
+    }
+
+    fn broken() -> Solution {
+        panic!("broken")
+    }
+
+    fn unwritten() -> Solution {
+        helpers::unimplemented()
+    }
+
+    #[test]
+    fn test_run_one() {
+        let result = run_one(1, broken, None);
+        assert_eq!(result.outcome, Outcome::Failed("broken".to_string()));
+        let result = run_one(2, unwritten, None);
+        assert_eq!(result.outcome, Outcome::Unimplemented);
+    }
+
+    #[test]
+    fn test_determinism() {
+        let jobs: Vec<(u8, Solver)> = vec![(1, steady), (2, flaky)];
+        let variations = check_determinism(jobs, 4, 1, 2, None);
+        assert_eq!(variations.len(), 2);
+        assert!(variations[0].is_deterministic());
+        assert_eq!(variations[1].outcomes.len(), 2);
+    }
+}
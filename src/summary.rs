@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use crate::config::Format;
+use crate::runner::{DayResult, Outcome};
+
+/// Number of days listed in the "slowest days" section.
+const SLOWEST: usize = 5;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PartCounts {
+    pub solved: usize,
+    pub failed: usize,
+    pub unimplemented: usize,
+}
+
+#[derive(Debug)]
+struct Row {
+    day: u8,
+    status: &'static str,
+    parts: Vec<Duration>,
+    total: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct Summary {
+    rows: Vec<Row>,
+    total: Duration,
+    wall: Duration,
+    counts: PartCounts,
+}
+
+fn status(outcome: &Outcome) -> &'static str {
+    match outcome {
+        Outcome::Solved(_) => "solved",
+        Outcome::Failed(_) => "failed",
+        Outcome::Unimplemented => "unimplemented",
+        Outcome::TimedOut => "timeout",
+    }
+}
+
+pub fn fmt_duration(d: Duration) -> String {
+    let ns = d.as_nanos();
+    match ns {
+        0..=999 => format!("{} ns", ns),
+        1_000..=999_999 => format!("{:.1} µs", ns as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1} ms", ns as f64 / 1e6),
+        _ => format!("{:.2} s", d.as_secs_f64()),
+    }
+}
+
+impl Summary {
+    /// `missing` are selected days without a solver, `wall` the time the
+    /// whole run took.
+    pub fn new(results: &[DayResult], missing: &[u8], wall: Duration) -> Summary {
+        let mut counts = PartCounts::default();
+        let mut rows = Vec::new();
+
+        for result in results {
+            // A part that returned may still have returned an error, so only
+            // an unimplemented day's finished parts count as solved.
+            let finished = match result.outcome {
+                Outcome::Solved(_) => 2,
+                Outcome::Unimplemented => result.parts.len().min(2),
+                Outcome::Failed(_) | Outcome::TimedOut => 0,
+            };
+            counts.solved += finished;
+            match result.outcome {
+                Outcome::Solved(_) => (),
+                Outcome::Unimplemented => counts.unimplemented += 2 - finished,
+                Outcome::Failed(_) | Outcome::TimedOut => counts.failed += 2 - finished,
+            }
+            rows.push(Row {
+                day: result.day,
+                status: status(&result.outcome),
+                parts: result.parts.clone(),
+                total: matches!(result.outcome, Outcome::Solved(_)).then_some(result.elapsed),
+            });
+        }
+
+        for &day in missing {
+            counts.unimplemented += 2;
+            rows.push(Row {
+                day,
+                status: "unimplemented",
+                parts: Vec::new(),
+                total: None,
+            });
+        }
+        rows.sort_by_key(|row| row.day);
+
+        let total = rows.iter().filter_map(|row| row.total).sum();
+        Summary {
+            rows,
+            total,
+            wall,
+            counts,
+        }
+    }
+
+    fn share(&self, d: Duration) -> f64 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        100.0 * d.as_secs_f64() / self.total.as_secs_f64()
+    }
+
+    fn slowest(&self) -> Vec<&Row> {
+        let mut rows: Vec<&Row> = self.rows.iter().filter(|row| row.total.is_some()).collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.total));
+        rows.truncate(SLOWEST);
+        rows
+    }
+
+    pub fn print(&self, format: Format) {
+        match format {
+            Format::Text => self.print_text(),
+            Format::Json => self.print_json(),
+        }
+    }
+
+    fn print_text(&self) {
+        println!("\n=== Summary ===");
+        println!(
+            "{:>3}  {:<13} {:>10} {:>10} {:>10} {:>7}",
+            "Day", "Status", "Part 1", "Part 2", "Total", "Share"
+        );
+        for row in &self.rows {
+            let (part1, part2) = match row.parts[..] {
+                [] => ("-".to_string(), "-".to_string()),
+                [both] if row.total.is_some() => (fmt_duration(both), "(joint)".to_string()),
+                [part1] => (fmt_duration(part1), "-".to_string()),
+                [part1, part2, ..] => (fmt_duration(part1), fmt_duration(part2)),
+            };
+            let (total, share) = match row.total {
+                Some(total) => (fmt_duration(total), format!("{:.1}%", self.share(total))),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            println!(
+                "{:>3}  {:<13} {:>10} {:>10} {:>10} {:>7}",
+                format!("{:02}", row.day),
+                row.status,
+                part1,
+                part2,
+                total,
+                share
+            );
+        }
+
+        println!("\nSlowest days:");
+        for (rank, row) in self.slowest().into_iter().enumerate() {
+            let total = row.total.unwrap_or_default();
+            println!(
+                "  {}. Day {:02}  {:>10} {:>6.1}%",
+                rank + 1,
+                row.day,
+                fmt_duration(total),
+                self.share(total)
+            );
+        }
+
+        let counts = &self.counts;
+        println!(
+            "\nParts: {} solved, {} failed, {} unimplemented",
+            counts.solved, counts.failed, counts.unimplemented
+        );
+        println!("\n=============");
+        println!("Overall Time: {:.4} ms", self.wall.as_millis());
+    }
+
+    fn print_json(&self) {
+        let ns = |d: Option<Duration>| d.map_or("null".to_string(), |d| d.as_nanos().to_string());
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let joint = row.parts.len() == 1 && row.total.is_some();
+                format!(
+                    "{{\"day\":{},\"status\":\"{}\",\"part1_ns\":{},\"part2_ns\":{},\"joint\":{},\"total_ns\":{},\"share\":{:.2}}}",
+                    row.day,
+                    row.status,
+                    ns(row.parts.first().copied()),
+                    ns(row.parts.get(1).copied()),
+                    joint,
+                    ns(row.total),
+                    row.total.map_or(0.0, |t| self.share(t))
+                )
+            })
+            .collect();
+        let slowest: Vec<String> = self
+            .slowest()
+            .iter()
+            .map(|row| row.day.to_string())
+            .collect();
+        let counts = &self.counts;
+        println!(
+            "{{\"summary\":{{\"days\":[{}],\"slowest\":[{}],\"solved\":{},\"failed\":{},\"unimplemented\":{},\"overall_ms\":{}}}}}",
+            rows.join(","),
+            slowest.join(","),
+            counts.solved,
+            counts.failed,
+            counts.unimplemented,
+            self.wall.as_millis()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(day: u8, outcome: Outcome, parts: &[u64]) -> DayResult {
+        let parts: Vec<Duration> = parts.iter().map(|&ms| Duration::from_millis(ms)).collect();
+        DayResult {
+            day,
+            outcome,
+            elapsed: parts.iter().sum(),
+            parts,
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let solved = Outcome::Solved(["1".to_string(), "2".to_string()]);
+        let results = [
+            result(1, solved.clone(), &[10, 30]),
+            result(2, solved, &[60]),
+            result(3, Outcome::Unimplemented, &[5]),
+            result(4, Outcome::Failed("oops".to_string()), &[]),
+            result(5, Outcome::Failed("balanced".to_string()), &[1, 2]),
+        ];
+        let summary = Summary::new(&results, &[25], Duration::from_millis(120));
+
+        let counts = PartCounts {
+            solved: 5,
+            failed: 4,
+            unimplemented: 3,
+        };
+        assert_eq!(summary.counts, counts);
+        assert_eq!(summary.total, Duration::from_millis(100));
+        assert_eq!(summary.rows.len(), 6);
+
+        let slowest: Vec<u8> = summary.slowest().iter().map(|row| row.day).collect();
+        assert_eq!(slowest, [2, 1]);
+        assert_eq!(summary.share(Duration::from_millis(60)), 60.0);
+    }
+
+    #[test]
+    fn test_fmt_duration() {
+        assert_eq!(fmt_duration(Duration::from_nanos(420)), "420 ns");
+        assert_eq!(fmt_duration(Duration::from_micros(1500)), "1.5 ms");
+        assert_eq!(fmt_duration(Duration::from_secs(3)), "3.00 s");
+    }
+}